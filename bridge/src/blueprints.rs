@@ -0,0 +1,179 @@
+// Blueprint/prefab subsystem: treats `.glb`/`.gltf` files in a library folder as named,
+// reusable prefabs that can be spawned repeatedly without re-reading the file each time.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::primitives::Aabb;
+
+/// Resolves a glTF-relative URI (an external buffer or image referenced from a `.gltf`
+/// file) to its bytes. Lets callers decouple resource fetching from disk layout -- e.g. to
+/// pull buffers out of an archive or over the network instead of from the filesystem.
+pub trait UriResolver: Send + Sync {
+    fn resolve(&self, uri: &str, base: &Path) -> io::Result<Vec<u8>>;
+}
+
+/// Default resolver: reads `uri` as a path relative to the glTF file's directory.
+pub struct FilesystemResolver;
+
+impl UriResolver for FilesystemResolver {
+    fn resolve(&self, uri: &str, base: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(base.join(uri))
+    }
+}
+
+/// Which container format `library_folder` entries are stored in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BlueprintFormat {
+    Glb,
+    Gltf,
+}
+
+impl BlueprintFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            BlueprintFormat::Glb => "glb",
+            BlueprintFormat::Gltf => "gltf",
+        }
+    }
+}
+
+pub struct BlueprintsConfig {
+    pub library_folder: PathBuf,
+    pub format: BlueprintFormat,
+    /// When set, `get` also computes (and memoizes) an AABB for the blueprint the first
+    /// time it's loaded, mirroring `embedded_models::set_aabb_caching`.
+    pub compute_aabb: bool,
+}
+
+/// A resolved blueprint: the scene file plus any authored component data found alongside it
+/// in a `<name>.components.json` sidecar (merged onto the spawned entities by the caller, via
+/// [`Blueprint::component_overrides`]).
+#[derive(Clone)]
+pub struct Blueprint {
+    pub path: PathBuf,
+    pub component_data: Option<String>,
+}
+
+/// Per-entity field overrides authored in a blueprint's `<name>.components.json` sidecar,
+/// applied on top of a spawned node's defaults by `sdxr_spawn_blueprint`. Mirrors the subset
+/// of `Node`'s fields that make sense to author per-blueprint rather than per-spawn.
+#[derive(Default, Clone, serde::Deserialize)]
+pub struct ComponentOverrides {
+    pub entity_type: Option<u8>,
+    pub color: Option<[f32; 4]>,
+    pub dimensions: Option<[f32; 3]>,
+    pub texture_url: Option<String>,
+}
+
+impl Blueprint {
+    /// Parses `component_data` (if present) into typed field overrides. `None` if there's no
+    /// sidecar, or it isn't valid JSON for [`ComponentOverrides`]'s shape.
+    pub fn component_overrides(&self) -> Option<ComponentOverrides> {
+        serde_json::from_str(self.component_data.as_ref()?).ok()
+    }
+}
+
+/// Caches blueprints by name so repeated spawns don't re-read the file from disk.
+pub struct BlueprintLibrary {
+    config: BlueprintsConfig,
+    resolver: Arc<dyn UriResolver>,
+    cache: Mutex<HashMap<String, Blueprint>>,
+    aabb_cache: Mutex<HashMap<String, Aabb>>,
+}
+
+impl BlueprintLibrary {
+    pub fn new(config: BlueprintsConfig) -> Self {
+        Self::with_resolver(config, Arc::new(FilesystemResolver))
+    }
+
+    /// Like `new`, but with a custom resolver for external buffer/image URIs referenced by
+    /// loaded `.gltf` files (archive members, virtual paths, remote fetches, ...).
+    pub fn with_resolver(config: BlueprintsConfig, resolver: Arc<dyn UriResolver>) -> Self {
+        Self { config, resolver, cache: Mutex::new(HashMap::new()), aabb_cache: Mutex::new(HashMap::new()) }
+    }
+
+    /// Resolves an external buffer/image URI referenced by a loaded blueprint, relative to
+    /// its file's directory, through the configured [`UriResolver`].
+    pub fn resolve_uri(&self, blueprint: &Blueprint, uri: &str) -> io::Result<Vec<u8>> {
+        let base = blueprint.path.parent().unwrap_or_else(|| Path::new(""));
+        self.resolver.resolve(uri, base)
+    }
+
+    /// Which container format `get` resolves names to. `sdxr_spawn_blueprint` consults this to
+    /// pick between `gltf_loader::load_and_cache` (`.glb`) and `gltf_loader::load_gltf_file`
+    /// (`.gltf`, which needs `resolver` too).
+    pub fn format(&self) -> BlueprintFormat {
+        self.config.format
+    }
+
+    /// The resolver configured for this library's external buffer/image URIs. Exposed so
+    /// `sdxr_spawn_blueprint` can pass it straight to `gltf_loader::load_gltf_file`.
+    pub fn resolver(&self) -> &Arc<dyn UriResolver> {
+        &self.resolver
+    }
+
+    /// Resolves `name` to a file under `library_folder`, loading (and caching) it on first
+    /// use. Returns `None` if no matching file exists.
+    pub fn get(&self, name: &str) -> Option<Blueprint> {
+        if let Some(hit) = self.cache.lock().unwrap().get(name) {
+            return Some(hit.clone());
+        }
+
+        let path = self.config.library_folder.join(format!("{}.{}", name, self.config.format.extension()));
+        if !path.exists() {
+            eprintln!("[blueprints] No blueprint named '{}' in {}", name, self.config.library_folder.display());
+            return None;
+        }
+
+        let component_data = std::fs::read_to_string(path.with_extension("components.json")).ok();
+        let blueprint = Blueprint { path, component_data };
+        self.cache.lock().unwrap().insert(name.to_string(), blueprint.clone());
+
+        if self.config.compute_aabb {
+            self.compute_aabb(name, &blueprint);
+        }
+
+        Some(blueprint)
+    }
+
+    /// Bounds for a blueprint previously loaded with `compute_aabb` enabled. Computed once
+    /// per load and reused by later cache hits; `invalidate` drops it along with the cached
+    /// blueprint, so the next `get` computes it again.
+    pub fn aabb_for(&self, name: &str) -> Option<Aabb> {
+        self.aabb_cache.lock().unwrap().get(name).copied()
+    }
+
+    fn compute_aabb(&self, name: &str, blueprint: &Blueprint) {
+        let loaded = match self.config.format {
+            BlueprintFormat::Glb => crate::gltf_loader::load_glb_file(&blueprint.path),
+            BlueprintFormat::Gltf => crate::gltf_loader::load_gltf_file(&blueprint.path, self.resolver.as_ref()),
+        };
+        match loaded {
+            Ok(mesh) => {
+                self.aabb_cache.lock().unwrap().insert(name.to_string(), mesh.bounds());
+            }
+            Err(e) => eprintln!("[blueprints] Failed to compute AABB for '{}': {:?}", name, e),
+        }
+    }
+
+    /// Drops a cached entry so the next `get` re-reads it from disk (e.g. after the author
+    /// edits the file in the library folder).
+    pub fn invalidate(&self, name: &str) {
+        self.cache.lock().unwrap().remove(name);
+        self.aabb_cache.lock().unwrap().remove(name);
+    }
+
+    /// The crate's own embedded cube/sphere primitives, exposed as built-in blueprints so
+    /// callers can spawn them by name like user-authored ones. `get` does not consult this;
+    /// `sdxr_spawn_blueprint` falls back to it when `get` finds nothing.
+    pub fn builtin(name: &str) -> Option<PathBuf> {
+        match name {
+            "cube" => Some(crate::primitives::embedded_models::get_cube_model()),
+            "sphere" => Some(crate::primitives::embedded_models::get_sphere_model()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,123 @@
+// Event subsystem: structured notifications for things the host previously could only learn
+// about by reading stderr -- a model download progressing, finishing, or failing, the
+// compositor connection dropping, a node changing underneath the host. Emitted from the
+// `reify` model-loading path (via `model_downloader`) and the connect/retry and
+// command-handling tasks in `sdxr_start_ex`, and delivered either by callback
+// (`sdxr_set_event_callback`) or by polling (`sdxr_poll_event`) for hosts that can't take a
+// callback across the FFI boundary.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Discriminant for [`EventRecord::kind`]. Only the fields documented for each kind below are
+/// meaningful; the rest are zeroed.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EventKind {
+    /// `c_id`, `bytes`, `total`.
+    ModelDownloadProgress = 0,
+    /// `c_id`.
+    ModelReady = 1,
+    /// `c_id`, `code`.
+    ModelFailed = 2,
+    /// No fields.
+    ConnectionLost = 3,
+    /// `c_id`.
+    NodeChanged = 4,
+}
+
+/// C-ABI event record delivered to `sdxr_set_event_callback` or filled in by `sdxr_poll_event`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EventRecord {
+    pub kind: EventKind,
+    pub c_id: u64,
+    pub bytes: u64,
+    pub total: u64,
+    pub code: i32,
+}
+
+/// Rust-side event, converted to the FFI-stable [`EventRecord`] at delivery time.
+pub(crate) enum Event {
+    ModelDownloadProgress { c_id: u64, bytes: u64, total: u64 },
+    ModelReady { c_id: u64 },
+    ModelFailed { c_id: u64, code: i32 },
+    ConnectionLost,
+    NodeChanged { c_id: u64 },
+}
+
+impl Event {
+    fn to_record(&self) -> EventRecord {
+        match *self {
+            Event::ModelDownloadProgress { c_id, bytes, total } => {
+                EventRecord { kind: EventKind::ModelDownloadProgress, c_id, bytes, total, code: 0 }
+            }
+            Event::ModelReady { c_id } => {
+                EventRecord { kind: EventKind::ModelReady, c_id, bytes: 0, total: 0, code: 0 }
+            }
+            Event::ModelFailed { c_id, code } => {
+                EventRecord { kind: EventKind::ModelFailed, c_id, bytes: 0, total: 0, code }
+            }
+            Event::ConnectionLost => {
+                EventRecord { kind: EventKind::ConnectionLost, c_id: 0, bytes: 0, total: 0, code: 0 }
+            }
+            Event::NodeChanged { c_id } => {
+                EventRecord { kind: EventKind::NodeChanged, c_id, bytes: 0, total: 0, code: 0 }
+            }
+        }
+    }
+}
+
+/// Bounds the poll queue so a host that never registers a callback and never polls doesn't
+/// leak memory -- the oldest queued event is dropped once it's full.
+const MAX_QUEUED_EVENTS: usize = 256;
+
+pub(crate) type EventCallback = extern "C" fn(*const EventRecord);
+
+lazy_static::lazy_static! {
+    static ref CALLBACK: Mutex<Option<EventCallback>> = Mutex::new(None);
+    /// Drained by `sdxr_poll_event`; only filled while no callback is registered, since a
+    /// registered callback receives every event directly as it's emitted.
+    static ref QUEUE: Mutex<VecDeque<EventRecord>> = Mutex::new(VecDeque::new());
+}
+
+/// Delivers `event` to the registered callback, or enqueues it for `sdxr_poll_event` if no
+/// callback is registered.
+pub(crate) fn emit(event: Event) {
+    let record = event.to_record();
+    let callback = *CALLBACK.lock().unwrap();
+    match callback {
+        Some(cb) => cb(&record as *const EventRecord),
+        None => {
+            let mut queue = QUEUE.lock().unwrap();
+            if queue.len() >= MAX_QUEUED_EVENTS {
+                queue.pop_front();
+            }
+            queue.push_back(record);
+        }
+    }
+}
+
+/// Registers `callback` to be invoked synchronously, on whichever thread emits the event, for
+/// every event from here on. Passing `None` unregisters it and switches back to poll delivery.
+pub(crate) fn set_callback(callback: Option<EventCallback>) {
+    *CALLBACK.lock().unwrap() = callback;
+    // Switching to callback delivery drops anything still queued, so switching back to polling
+    // later starts from an empty queue rather than replaying events from before the callback
+    // was registered.
+    if callback.is_some() {
+        QUEUE.lock().unwrap().clear();
+    }
+}
+
+/// Pops the oldest queued event into `*out`, returning 1, or returns 0 if none is queued.
+/// Events are only queued while no callback is registered.
+pub(crate) fn poll(out: *mut EventRecord) -> i32 {
+    match QUEUE.lock().unwrap().pop_front() {
+        Some(record) => {
+            unsafe { *out = record; }
+            1
+        }
+        None => 0,
+    }
+}
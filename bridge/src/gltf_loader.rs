@@ -0,0 +1,450 @@
+// glTF/GLB model loader: parses a self-contained `.glb` container (12-byte header, a JSON
+// chunk, and an optional BIN chunk) into flat vertex/index buffers plus the first material's
+// base color and base-color texture, so `sdxr_set_node_model_bytes` can hand the host real
+// mesh data instead of leaving it to guess the format from a raw URL string. Parsed meshes are
+// content-addressed (same idea as `model_downloader`'s cache) and cached here, keyed by the
+// `u64` handle carried in `Command::SetModelMesh`.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::blueprints::UriResolver;
+use crate::primitives::Aabb;
+
+const GLB_MAGIC: u32 = 0x46546C67;
+const GLB_VERSION: u32 = 2;
+const CHUNK_TYPE_JSON: u32 = 0x4E4F534A;
+const CHUNK_TYPE_BIN: u32 = 0x004E4942;
+
+/// Why a `.glb` buffer was rejected, surfaced as a distinct negative code by
+/// `sdxr_set_node_model_bytes`.
+#[derive(Debug)]
+pub enum GltfError {
+    Truncated,
+    BadMagic,
+    UnsupportedVersion(u32),
+    MissingJsonChunk,
+    InvalidJson(String),
+    MissingAccessor(&'static str),
+    Unsupported(&'static str),
+    Io(String),
+}
+
+impl GltfError {
+    /// Stable FFI error code; see `sdxr_set_node_model_bytes`'s doc comment for the full table.
+    pub fn code(&self) -> i32 {
+        match self {
+            GltfError::Truncated => -3,
+            GltfError::BadMagic => -4,
+            GltfError::UnsupportedVersion(_) => -5,
+            GltfError::MissingJsonChunk => -6,
+            GltfError::InvalidJson(_) => -7,
+            GltfError::MissingAccessor(_) => -8,
+            GltfError::Unsupported(_) => -9,
+            GltfError::Io(_) => -10,
+        }
+    }
+}
+
+/// A material's base-color texture, as the raw encoded image bytes sliced out of the GLB's
+/// BIN chunk (PNG/JPEG, whichever the source embedded) -- decoding is left to the renderer.
+#[derive(Clone)]
+pub struct TextureRef {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Flat vertex/index buffers for one glTF primitive, plus the material it's drawn with.
+/// Mirrors `primitives::embedded_models::mesh::MeshData` but uses `u32` indices since an
+/// externally-authored mesh isn't bounded by our own generator's `u16` vertex count.
+#[derive(Clone)]
+pub struct LoadedMesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+    pub base_color: [f32; 4],
+    pub base_color_texture: Option<TextureRef>,
+}
+
+impl LoadedMesh {
+    pub fn bounds(&self) -> Aabb {
+        let mut min = self.positions[0];
+        let mut max = self.positions[0];
+        for p in &self.positions {
+            for i in 0..3 {
+                min[i] = min[i].min(p[i]);
+                max[i] = max[i].max(p[i]);
+            }
+        }
+        Aabb { min, max }
+    }
+}
+
+static MESH_CACHE: OnceLock<Mutex<HashMap<u64, Arc<LoadedMesh>>>> = OnceLock::new();
+
+fn mesh_cache() -> &'static Mutex<HashMap<u64, Arc<LoadedMesh>>> {
+    MESH_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Content-addresses `bytes`, truncated to a `u64` since `Command::SetModelMesh` only needs a
+/// cache key, not a full digest.
+pub fn content_key(bytes: &[u8]) -> u64 {
+    let digest = Sha256::digest(bytes);
+    u64::from_le_bytes(digest[0..8].try_into().unwrap())
+}
+
+/// Looks up a mesh previously parsed and cached under `handle`.
+pub fn get_cached(handle: u64) -> Option<Arc<LoadedMesh>> {
+    mesh_cache().lock().unwrap().get(&handle).cloned()
+}
+
+/// Parses `bytes` as a `.glb` container and caches the result under its content key,
+/// returning that key. Re-parsing the same bytes is a cache hit.
+pub fn load_and_cache(bytes: &[u8]) -> Result<u64, GltfError> {
+    let key = content_key(bytes);
+    if mesh_cache().lock().unwrap().contains_key(&key) {
+        return Ok(key);
+    }
+    let mesh = parse_glb(bytes)?;
+    mesh_cache().lock().unwrap().insert(key, Arc::new(mesh));
+    Ok(key)
+}
+
+/// Parses a `.glb` file from disk. Used by `BlueprintLibrary::compute_aabb` for blueprints
+/// stored as `BlueprintFormat::Glb`.
+pub fn load_glb_file(path: &Path) -> Result<LoadedMesh, GltfError> {
+    let bytes = std::fs::read(path).map_err(|e| GltfError::Io(e.to_string()))?;
+    parse_glb(&bytes)
+}
+
+/// Inserts an already-parsed mesh into the cache under a content key derived from
+/// `key_bytes` (typically the source file's raw bytes), for loaders like `load_gltf_file`
+/// that parse something other than raw `.glb` bytes and so can't go through `load_and_cache`.
+pub fn cache_parsed(mesh: LoadedMesh, key_bytes: &[u8]) -> u64 {
+    let key = content_key(key_bytes);
+    mesh_cache().lock().unwrap().entry(key).or_insert_with(|| Arc::new(mesh));
+    key
+}
+
+/// Parses a `.gltf` (JSON, not self-contained `.glb`) file from disk, resolving its buffer and
+/// any texture image through `resolver` -- every non-embedded URI a well-formed `.gltf` can
+/// reference. Only the first `buffers` entry is read, mirroring `resolve_first_mesh`'s own
+/// single-mesh assumption; a multi-buffer `.gltf` isn't supported yet.
+pub fn load_gltf_file(path: &Path, resolver: &dyn UriResolver) -> Result<LoadedMesh, GltfError> {
+    let text = std::fs::read_to_string(path).map_err(|e| GltfError::Io(e.to_string()))?;
+    let root: Value = serde_json::from_str(&text).map_err(|e| GltfError::InvalidJson(e.to_string()))?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let bin = match root.get("buffers").and_then(Value::as_array).and_then(|b| b.first()) {
+        Some(buffer) => {
+            let uri = buffer
+                .get("uri")
+                .and_then(Value::as_str)
+                .ok_or(GltfError::Unsupported("buffer with no uri (embedded GLB-style buffers aren't supported by load_gltf_file)"))?;
+            resolver.resolve(uri, base).map_err(|e| GltfError::Io(e.to_string()))?
+        }
+        None => Vec::new(),
+    };
+
+    let ctx = ResolveCtx { resolver, base };
+    resolve_first_mesh(&root, &bin, Some(&ctx))
+}
+
+/// Threaded through parsing so external (non-embedded) buffer/image URIs can be resolved.
+/// `None` when parsing a self-contained `.glb`, which has nothing left to resolve externally.
+struct ResolveCtx<'a> {
+    resolver: &'a dyn UriResolver,
+    base: &'a Path,
+}
+
+/// Parses the 12-byte GLB header, its JSON chunk, and optional BIN chunk, then resolves the
+/// first mesh reachable from `nodes` into flat vertex/index buffers and its material.
+pub fn parse_glb(bytes: &[u8]) -> Result<LoadedMesh, GltfError> {
+    if bytes.len() < 12 {
+        return Err(GltfError::Truncated);
+    }
+    let magic = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+    if magic != GLB_MAGIC {
+        return Err(GltfError::BadMagic);
+    }
+    let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    if version != GLB_VERSION {
+        return Err(GltfError::UnsupportedVersion(version));
+    }
+    let total_length = u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize;
+    if bytes.len() < total_length {
+        return Err(GltfError::Truncated);
+    }
+
+    let mut json: Option<&[u8]> = None;
+    let mut bin: Option<&[u8]> = None;
+    let mut pos = 12;
+    while pos + 8 <= total_length {
+        let chunk_len = u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+        let chunk_type = u32::from_le_bytes(bytes[pos + 4..pos + 8].try_into().unwrap());
+        let data_start = pos + 8;
+        let data_end = data_start + chunk_len;
+        if data_end > total_length {
+            return Err(GltfError::Truncated);
+        }
+        let data = &bytes[data_start..data_end];
+        match chunk_type {
+            CHUNK_TYPE_JSON => json = Some(data),
+            CHUNK_TYPE_BIN => bin = Some(data),
+            _ => {} // unknown chunk types are skipped, per the GLB spec
+        }
+        pos = data_end;
+    }
+
+    let json = json.ok_or(GltfError::MissingJsonChunk)?;
+    let root: Value = serde_json::from_slice(json).map_err(|e| GltfError::InvalidJson(e.to_string()))?;
+    let bin = bin.unwrap_or(&[]);
+
+    resolve_first_mesh(&root, bin, None)
+}
+
+/// Finds the first node that references a mesh and resolves that mesh's first primitive.
+/// Mirrors `glb_writer::write_glb`'s own single-primitive assumption rather than flattening
+/// every node/primitive in the scene into one buffer.
+fn resolve_first_mesh(root: &Value, bin: &[u8], ctx: Option<&ResolveCtx>) -> Result<LoadedMesh, GltfError> {
+    let mesh_index = root
+        .get("nodes")
+        .and_then(Value::as_array)
+        .and_then(|nodes| nodes.iter().find_map(|n| n.get("mesh").and_then(Value::as_u64)))
+        .ok_or(GltfError::MissingAccessor("no node references a mesh"))?;
+
+    let mesh = root
+        .get("meshes")
+        .and_then(Value::as_array)
+        .and_then(|meshes| meshes.get(mesh_index as usize))
+        .ok_or(GltfError::MissingAccessor("meshes"))?;
+    let primitive = mesh
+        .get("primitives")
+        .and_then(Value::as_array)
+        .and_then(|prims| prims.first())
+        .ok_or(GltfError::MissingAccessor("primitives"))?;
+
+    let attributes = primitive.get("attributes").ok_or(GltfError::MissingAccessor("attributes"))?;
+    let positions =
+        read_vec3_accessor(root, bin, attributes, "POSITION")?.ok_or(GltfError::MissingAccessor("POSITION"))?;
+    let normals = read_vec3_accessor(root, bin, attributes, "NORMAL")?
+        .unwrap_or_else(|| vec![[0.0, 1.0, 0.0]; positions.len()]);
+    let uvs = read_vec2_accessor(root, bin, attributes, "TEXCOORD_0")?.unwrap_or_else(|| vec![[0.0, 0.0]; positions.len()]);
+
+    let indices_accessor = primitive.get("indices").and_then(Value::as_u64).ok_or(GltfError::MissingAccessor("indices"))?;
+    let indices = read_index_accessor(root, bin, indices_accessor as usize)?;
+
+    let (base_color, base_color_texture) = match primitive.get("material").and_then(Value::as_u64) {
+        Some(material_index) => resolve_material(root, bin, material_index as usize, ctx)?,
+        None => ([1.0, 1.0, 1.0, 1.0], None),
+    };
+
+    Ok(LoadedMesh { positions, normals, uvs, indices, base_color, base_color_texture })
+}
+
+fn accessor(root: &Value, index: usize) -> Result<&Value, GltfError> {
+    root.get("accessors")
+        .and_then(Value::as_array)
+        .and_then(|a| a.get(index))
+        .ok_or(GltfError::MissingAccessor("accessor index out of range"))
+}
+
+fn buffer_view(root: &Value, index: usize) -> Result<&Value, GltfError> {
+    root.get("bufferViews")
+        .and_then(Value::as_array)
+        .and_then(|v| v.get(index))
+        .ok_or(GltfError::MissingAccessor("bufferView index out of range"))
+}
+
+/// Resolves accessor `index` to `(data start within `bin`, element count, byte stride between
+/// elements (0 if tightly packed), componentType)`.
+fn accessor_layout(root: &Value, bin: &[u8], index: usize) -> Result<(usize, usize, usize, u64), GltfError> {
+    let acc = accessor(root, index)?;
+    let buffer_view_index =
+        acc.get("bufferView").and_then(Value::as_u64).ok_or(GltfError::MissingAccessor("bufferView"))? as usize;
+    let component_type =
+        acc.get("componentType").and_then(Value::as_u64).ok_or(GltfError::MissingAccessor("componentType"))?;
+    let count = acc.get("count").and_then(Value::as_u64).ok_or(GltfError::MissingAccessor("count"))? as usize;
+    let accessor_offset = acc.get("byteOffset").and_then(Value::as_u64).unwrap_or(0) as usize;
+
+    let bv = buffer_view(root, buffer_view_index)?;
+    let bv_offset = bv.get("byteOffset").and_then(Value::as_u64).unwrap_or(0) as usize;
+    let stride = bv.get("byteStride").and_then(Value::as_u64).unwrap_or(0) as usize;
+
+    let start = bv_offset + accessor_offset;
+    if start > bin.len() {
+        return Err(GltfError::Truncated);
+    }
+    Ok((start, count, stride, component_type))
+}
+
+fn component_size(component_type: u64) -> Result<usize, GltfError> {
+    match component_type {
+        5120 | 5121 => Ok(1), // BYTE / UNSIGNED_BYTE
+        5122 | 5123 => Ok(2), // SHORT / UNSIGNED_SHORT
+        5125 | 5126 => Ok(4), // UNSIGNED_INT / FLOAT
+        _ => Err(GltfError::Unsupported("componentType")),
+    }
+}
+
+/// Reads `components`-wide `FLOAT` elements starting at accessor `accessor_index`. Other
+/// component types are rejected -- POSITION/NORMAL/TEXCOORD_0 are always `FLOAT` in a
+/// well-formed glTF, so this matches every real-world exporter without extra conversion paths.
+fn read_float_components(root: &Value, bin: &[u8], accessor_index: usize, components: usize) -> Result<Vec<f32>, GltfError> {
+    let (start, count, stride, component_type) = accessor_layout(root, bin, accessor_index)?;
+    if component_type != 5126 {
+        return Err(GltfError::Unsupported("non-FLOAT POSITION/NORMAL/TEXCOORD accessor"));
+    }
+    let elem_size = components * 4;
+    let stride = if stride == 0 { elem_size } else { stride };
+    let mut out = Vec::with_capacity(count * components);
+    for i in 0..count {
+        let elem_start = start + i * stride;
+        let bytes = bin.get(elem_start..elem_start + elem_size).ok_or(GltfError::Truncated)?;
+        for c in 0..components {
+            let v: [u8; 4] = bytes[c * 4..c * 4 + 4].try_into().unwrap();
+            out.push(f32::from_le_bytes(v));
+        }
+    }
+    Ok(out)
+}
+
+fn read_vec3_accessor(root: &Value, bin: &[u8], attributes: &Value, name: &str) -> Result<Option<Vec<[f32; 3]>>, GltfError> {
+    let Some(index) = attributes.get(name).and_then(Value::as_u64) else { return Ok(None) };
+    let flat = read_float_components(root, bin, index as usize, 3)?;
+    Ok(Some(flat.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect()))
+}
+
+fn read_vec2_accessor(root: &Value, bin: &[u8], attributes: &Value, name: &str) -> Result<Option<Vec<[f32; 2]>>, GltfError> {
+    let Some(index) = attributes.get(name).and_then(Value::as_u64) else { return Ok(None) };
+    let flat = read_float_components(root, bin, index as usize, 2)?;
+    Ok(Some(flat.chunks_exact(2).map(|c| [c[0], c[1]]).collect()))
+}
+
+/// Reads an index accessor, widening `UNSIGNED_BYTE`/`UNSIGNED_SHORT`/`UNSIGNED_INT` indices
+/// to `u32` uniformly so `LoadedMesh::indices` doesn't need to track the source width.
+fn read_index_accessor(root: &Value, bin: &[u8], accessor_index: usize) -> Result<Vec<u32>, GltfError> {
+    let (start, count, stride, component_type) = accessor_layout(root, bin, accessor_index)?;
+    let elem_size = component_size(component_type)?;
+    let stride = if stride == 0 { elem_size } else { stride };
+    let mut out = Vec::with_capacity(count);
+    for i in 0..count {
+        let elem_start = start + i * stride;
+        let bytes = bin.get(elem_start..elem_start + elem_size).ok_or(GltfError::Truncated)?;
+        let value = match component_type {
+            5121 => bytes[0] as u32,
+            5123 => u16::from_le_bytes(bytes.try_into().unwrap()) as u32,
+            5125 => u32::from_le_bytes(bytes.try_into().unwrap()),
+            _ => return Err(GltfError::Unsupported("non-unsigned index componentType")),
+        };
+        out.push(value);
+    }
+    Ok(out)
+}
+
+fn resolve_material(
+    root: &Value,
+    bin: &[u8],
+    material_index: usize,
+    ctx: Option<&ResolveCtx>,
+) -> Result<([f32; 4], Option<TextureRef>), GltfError> {
+    let material = root
+        .get("materials")
+        .and_then(Value::as_array)
+        .and_then(|m| m.get(material_index))
+        .ok_or(GltfError::MissingAccessor("materials"))?;
+
+    let pbr = material.get("pbrMetallicRoughness");
+    let base_color = pbr
+        .and_then(|p| p.get("baseColorFactor"))
+        .and_then(Value::as_array)
+        .map(|arr| {
+            let mut color = [1.0f32; 4];
+            for (i, v) in arr.iter().take(4).enumerate() {
+                color[i] = v.as_f64().unwrap_or(1.0) as f32;
+            }
+            color
+        })
+        .unwrap_or([1.0, 1.0, 1.0, 1.0]);
+
+    let texture = pbr
+        .and_then(|p| p.get("baseColorTexture"))
+        .and_then(|t| t.get("index"))
+        .and_then(Value::as_u64)
+        .and_then(|texture_index| load_texture(root, bin, texture_index as usize, ctx));
+
+    Ok((base_color, texture))
+}
+
+/// Resolves a texture image, either embedded (`bufferView`-backed, as a self-contained `.glb`
+/// stores it) or referenced by an external `uri` (resolved through `ctx`, when parsing a
+/// `.gltf` with one). A `uri`-sourced image with no `ctx` (i.e. found while parsing a `.glb`,
+/// which has nowhere external to resolve it from) is skipped rather than treated as an error.
+fn load_texture(root: &Value, bin: &[u8], texture_index: usize, ctx: Option<&ResolveCtx>) -> Option<TextureRef> {
+    let source_index = root.get("textures")?.as_array()?.get(texture_index)?.get("source")?.as_u64()?;
+    let image = root.get("images")?.as_array()?.get(source_index as usize)?;
+    let mime_type = image.get("mimeType").and_then(Value::as_str).unwrap_or("image/png").to_string();
+
+    if let Some(buffer_view_index) = image.get("bufferView").and_then(Value::as_u64) {
+        let bv = buffer_view(root, buffer_view_index as usize).ok()?;
+        let offset = bv.get("byteOffset").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let length = bv.get("byteLength").and_then(Value::as_u64)? as usize;
+        let data = bin.get(offset..offset + length)?.to_vec();
+        return Some(TextureRef { mime_type, data });
+    }
+
+    let uri = image.get("uri").and_then(Value::as_str)?;
+    let ctx = ctx?;
+    let data = ctx.resolver.resolve(uri, ctx.base).ok()?;
+    Some(TextureRef { mime_type, data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::embedded_models::{glb_writer, mesh};
+
+    fn cube_glb() -> Vec<u8> {
+        glb_writer::write_glb(&mesh::cube())
+    }
+
+    #[test]
+    fn parses_a_well_formed_cube_glb() {
+        let mesh = parse_glb(&cube_glb()).expect("a freshly-written cube .glb should parse");
+        assert_eq!(mesh.positions.len(), 24); // 6 faces * 4 verts
+        assert_eq!(mesh.indices.len(), 36); // 6 faces * 2 tris * 3 verts
+        assert_eq!(mesh.base_color, [1.0, 1.0, 1.0, 1.0]);
+        assert!(mesh.base_color_texture.is_none());
+    }
+
+    #[test]
+    fn rejects_bytes_too_short_for_a_header() {
+        assert!(matches!(parse_glb(&[0u8; 4]), Err(GltfError::Truncated)));
+    }
+
+    #[test]
+    fn rejects_wrong_magic() {
+        let mut bytes = cube_glb();
+        bytes[0..4].copy_from_slice(&0xDEADBEEFu32.to_le_bytes());
+        assert!(matches!(parse_glb(&bytes), Err(GltfError::BadMagic)));
+    }
+
+    #[test]
+    fn rejects_a_length_claim_longer_than_the_buffer() {
+        let mut bytes = cube_glb();
+        let claimed_too_long = (bytes.len() as u32 + 1000).to_le_bytes();
+        bytes[8..12].copy_from_slice(&claimed_too_long);
+        assert!(matches!(parse_glb(&bytes), Err(GltfError::Truncated)));
+    }
+
+    #[test]
+    fn content_key_is_stable_for_identical_bytes() {
+        let bytes = cube_glb();
+        assert_eq!(content_key(&bytes), content_key(&bytes.clone()));
+    }
+}
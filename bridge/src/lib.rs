@@ -1,6 +1,17 @@
 // Rust C-ABI bridge for StardustXR client integration.
 
+mod blueprints;
+mod events;
+mod gltf_loader;
 mod model_downloader;
+mod networking;
+mod persistence;
+mod plugins;
+mod primitives;
+mod replication;
+mod scene_io;
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+mod wasm_bindings;
 
 use std::collections::HashMap;
 use std::ffi::CStr;
@@ -20,11 +31,24 @@ use stardust_xr_fusion::objects::connect_client as fusion_connect_client;
 use stardust_xr_fusion::node::NodeType;
 use stardust_xr_fusion::root::RootAspect;
 use tokio::runtime::Runtime;
-use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
+use std::path::{Path, PathBuf};
+use events::Event;
 use model_downloader::ModelDownloader;
+use persistence::{PersistenceBackend, SceneStore};
 
 // Global model downloader instance
 static MODEL_DOWNLOADER: OnceLock<ModelDownloader> = OnceLock::new();
+// Global scene persistence backend, shared between the startup restore path and the
+// background flush task spawned in `sdxr_start`. Defaults to `SceneStore` (SQLite) at its
+// default path if `sdxr_set_persistence_backend` isn't called before `sdxr_start`.
+static SCENE_STORE: OnceLock<Arc<dyn PersistenceBackend>> = OnceLock::new();
+
+fn scene_store() -> &'static Arc<dyn PersistenceBackend> {
+    SCENE_STORE.get_or_init(|| {
+        Arc::new(SceneStore::open(&SceneStore::default_path()).expect("Failed to open scene store"))
+    })
+}
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 struct BridgeState {
@@ -37,28 +61,81 @@ impl Default for BridgeState {
     }
 }
 
-enum Command {
-    Create { c_id: u64, name: String, transform: Mat4 },
-    Update { c_id: u64, transform: Mat4 },
-    SetModel { c_id: u64, model_url: String },
-    SetTexture { c_id: u64, texture_url: String },
-    SetColor { c_id: u64, color: [f32; 4] }, // RGBA
-    SetDimensions { c_id: u64, dimensions: [f32; 3] },
-    SetEntityType { c_id: u64, entity_type: u8 },
-    Remove { c_id: u64 },
+/// Mutations applied to `BridgeState`. Besides local FFI calls, these are also what gets
+/// replicated between paired bridges (see `networking`): each mutation carries this node's
+/// Lamport clock value so two peers editing the same `c_id` converge last-writer-wins.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) enum Command {
+    Create { c_id: u64, name: String, transform: Mat4, lamport: u64 },
+    Update { c_id: u64, transform: Mat4, lamport: u64 },
+    SetModel { c_id: u64, model_url: String, lamport: u64 },
+    /// Like `SetModel`, but for a `.glb` already parsed by `gltf_loader` -- `mesh_handle` is a
+    /// content-addressed key into its mesh cache rather than a URL the engine has to resolve
+    /// and guess the format of. Emitted by `sdxr_set_node_model_bytes`.
+    SetModelMesh { c_id: u64, mesh_handle: u64, lamport: u64 },
+    SetTexture { c_id: u64, texture_url: String, lamport: u64 },
+    SetColor { c_id: u64, color: [f32; 4], lamport: u64 }, // RGBA
+    SetDimensions { c_id: u64, dimensions: [f32; 3], lamport: u64 },
+    SetEntityType { c_id: u64, entity_type: u8, lamport: u64 },
+    Remove { c_id: u64, lamport: u64 },
     Shutdown,
 }
 
-// Connection status for startup
-static CONNECTION_SUCCESS: AtomicBool = AtomicBool::new(false);
-static CONNECTION_FAILED: AtomicBool = AtomicBool::new(false);
+impl Command {
+    fn c_id(&self) -> Option<u64> {
+        match self {
+            Command::Create { c_id, .. }
+            | Command::Update { c_id, .. }
+            | Command::SetModel { c_id, .. }
+            | Command::SetModelMesh { c_id, .. }
+            | Command::SetTexture { c_id, .. }
+            | Command::SetColor { c_id, .. }
+            | Command::SetDimensions { c_id, .. }
+            | Command::SetEntityType { c_id, .. }
+            | Command::Remove { c_id, .. } => Some(*c_id),
+            Command::Shutdown => None,
+        }
+    }
+
+    fn lamport(&self) -> Option<u64> {
+        match self {
+            Command::Create { lamport, .. }
+            | Command::Update { lamport, .. }
+            | Command::SetModel { lamport, .. }
+            | Command::SetModelMesh { lamport, .. }
+            | Command::SetTexture { lamport, .. }
+            | Command::SetColor { lamport, .. }
+            | Command::SetDimensions { lamport, .. }
+            | Command::SetEntityType { lamport, .. }
+            | Command::Remove { lamport, .. } => Some(*lamport),
+            Command::Shutdown => None,
+        }
+    }
+}
+
+/// Outcome reported by the connect task through the startup oneshot channel, replacing the
+/// old poll loop over a pair of `AtomicBool`s.
+enum ConnectResult {
+    Success,
+    Failed(String),
+    TimedOut,
+}
 
 impl Migrate for BridgeState { type Old = Self; }
 
 impl ClientState for BridgeState {
     const APP_ID: &'static str = "org.stardustxr.starworld";
-    fn initial_state_update(&mut self) {}
-    
+
+    fn initial_state_update(&mut self) {
+        match scene_store().load() {
+            Ok(snapshot) => {
+                eprintln!("[bridge] Restored {} node(s) from persisted scene (schema v{})", snapshot.nodes.len(), snapshot.version);
+                self.nodes = snapshot.nodes;
+            }
+            Err(e) => eprintln!("[bridge] Failed to load persisted scene: {}", e),
+        }
+    }
+
     fn on_frame(&mut self, _info: &stardust_xr_fusion::root::FrameInfo) {
         // Sync from the global shared state on each frame
         if let Ok(ctrl) = CTRL.lock() {
@@ -87,16 +164,21 @@ impl Reify for BridgeState {
             let cache_dir = dirs::cache_dir()
                 .unwrap_or_else(|| PathBuf::from("/tmp"))
                 .join("starworld/models");
-            ModelDownloader::new(cache_dir).expect("Failed to create model downloader")
+            // `reify` runs on the worker thread `sdxr_start_ex` spawned to drive the bridge's
+            // runtime, so its handle is always set by the time the first node is reified.
+            let runtime = CTRL.lock().unwrap().rt.as_ref().expect("bridge not started").handle().clone();
+            let auth_tokens = model_downloader::AuthTokens::from_env("STARWORLD_MODEL_AUTH_TOKENS");
+            ModelDownloader::new(cache_dir, model_downloader::CacheSetting::Revalidate, runtime, auth_tokens)
+                .expect("Failed to create model downloader")
         });
         
-        fn get_model_path(entity_type: u8, model_url: &str, downloader: &ModelDownloader) -> Option<PathBuf> {
+        fn get_model_path(c_id: u64, entity_type: u8, model_url: &str, downloader: &'static ModelDownloader) -> Option<PathBuf> {
             // First check if there's a model URL provided
             if !model_url.is_empty() {
                 // Handle HTTP/HTTPS URLs
                 if model_url.starts_with("http://") || model_url.starts_with("https://") {
                     eprintln!("[bridge/reify] Attempting to download model from URL: {}", model_url);
-                    if let Some(path) = downloader.get_model(model_url) {
+                    if let Some(path) = downloader.get_model(c_id, model_url) {
                         eprintln!("[bridge/reify] Using downloaded model: {}", path.display());
                         return Some(path);
                     } else {
@@ -150,7 +232,7 @@ impl Reify for BridgeState {
             let transform = stardust_xr_fusion::spatial::Transform::from_translation_rotation_scale(trans_array, rot_array, scale_array);
             
             // Try to load the appropriate model based on entity type and model URL
-            let model_child = if let Some(model_path) = get_model_path(node.entity_type, &node.model_url, downloader) {
+            let model_child = if let Some(model_path) = get_model_path(*id, node.entity_type, &node.model_url, downloader) {
                 let entity_type_name = match node.entity_type {
                     1 => "cube",
                     2 => "sphere",
@@ -168,7 +250,6 @@ impl Reify for BridgeState {
                     entity_type_name, id, model_source);
                 
                 match Model::direct(&model_path) {
-<<<<<<< HEAD
                     Ok(mut model) => {
                         // Asteroids Model now supports material color tinting.
                         if node.color != [1.0, 1.0, 1.0, 1.0] {
@@ -181,24 +262,19 @@ impl Reify for BridgeState {
                         }
 
                         // TODO: Apply texture from texture_url (pending API)
-=======
-                    Ok(model) => {
-                        // TODO: Color tinting is not currently supported due to missing public API in asteroids.
-                        // When Model/MaterialParameter API is available, apply color here.
-                        if node.color != [1.0, 1.0, 1.0, 1.0] {
-                            eprintln!("[bridge/reify] Node {} requested color tint RGBA({:.2}, {:.2}, {:.2}, {:.2}) -- NOT SUPPORTED YET", 
-                                id, node.color[0], node.color[1], node.color[2], node.color[3]);
-                        }
-                        // TODO: Apply texture from texture_url (future)
->>>>>>> 0a39697599277320e2650a938b695beeb401c931
                         if !node.texture_url.is_empty() {
                             eprintln!("[bridge/reify] Node {} has texture URL: {} - NOT YET APPLIED (API limitation)",
                                 id, node.texture_url);
                         }
-<<<<<<< HEAD
 
-=======
->>>>>>> 0a39697599277320e2650a938b695beeb401c931
+                        // TODO: Build a Model from the parsed vertex/index buffers directly
+                        // once Asteroids exposes that API; for now the node still renders
+                        // through `model_path` above and the parsed mesh is unused here.
+                        if let Some(handle) = node.model_mesh {
+                            eprintln!("[bridge/reify] Node {} has parsed mesh handle {:#x} - NOT YET RENDERED (API limitation)",
+                                id, handle);
+                        }
+
                         Some(model.build())
                     }
                     Err(e) => {
@@ -222,37 +298,129 @@ impl Reify for BridgeState {
 }
 
 static STARTED: AtomicBool = AtomicBool::new(false);
-static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
 lazy_static::lazy_static! {
     static ref CTRL: Mutex<Ctrl> = Mutex::new(Ctrl::default());
 }
 
+#[derive(Default)]
+struct Ctrl {
+    next_id: u64,
+    tx: Option<tokio::sync::mpsc::UnboundedSender<Command>>,
+    /// The runtime driving the command/persist/event-loop tasks, owned here for the whole
+    /// lifetime of the bridge rather than being moved into (and lost to) the worker thread.
+    rt: Option<Runtime>,
+    handle: Option<JoinHandle<()>>,
+    shared_state: Option<Arc<Mutex<BridgeState>>>,
+    nodes: HashMap<u64, Node>,
+    /// This instance's Lamport clock, ticked on every locally-originated mutation so
+    /// replicated commands can be ordered against remote peers' clocks.
+    lamport_clock: u64,
+    /// Per-node last-applied Lamport value, shared with the command task so both local
+    /// and replicated mutations resolve through the same last-writer-wins rule.
+    applied_lamport: Option<Arc<Mutex<HashMap<u64, u64>>>>,
+    peers: Option<Arc<networking::PeerNetwork>>,
+    /// Configured by `sdxr_set_blueprint_library`; consulted by `sdxr_spawn_blueprint`. `None`
+    /// until an embedder configures one -- built-in blueprints (`BlueprintLibrary::builtin`)
+    /// don't need it.
+    blueprints: Option<Arc<blueprints::BlueprintLibrary>>,
+    /// Send end of the dirty-scene channel drained by `persist_task`; every mutating
+    /// `Command` pushes the latest node snapshot here instead of writing to disk itself.
+    persist_tx: Option<tokio::sync::mpsc::UnboundedSender<HashMap<u64, Node>>>,
+    /// Cancelled on `sdxr_shutdown_ex` to signal the event loop and command task directly,
+    /// replacing the old `STOP_REQUESTED` flag poll.
+    cancel: Option<CancellationToken>,
+}
+
+impl Ctrl {
+    /// Advances and returns this instance's Lamport clock for a new locally-originated command.
+    fn tick(&mut self) -> u64 {
+        self.lamport_clock += 1;
+        self.lamport_clock
+    }
+}
+
 #[derive(Default, Clone, serde::Serialize, serde::Deserialize)]
 struct Node {
     id: u64,
     name: String,
-                        Ok(mut model) => {
-                            // Asteroids Model now supports material color tinting.
-                            if node.color != [1.0, 1.0, 1.0, 1.0] {
-                                let color = ast::elements::RgbaLinear::new(
-                                    node.color[0], node.color[1], node.color[2], node.color[3]
-                                );
-                                model = model.color_tint(color);
-                                eprintln!("[bridge/reify] Node {}: applied color tint RGBA({:.2}, {:.2}, {:.2}, {:.2})",
-                                    id, node.color[0], node.color[1], node.color[2], node.color[3]);
-                            }
+    transform: Mat4,
+    entity_type: u8,
+    model_url: String,
+    texture_url: String,
+    color: [f32; 4],
+    dimensions: [f32; 3],
+    /// Content-addressed handle into `gltf_loader`'s mesh cache, set by `SetModelMesh` for
+    /// nodes whose model was given as pre-parsed `.glb` bytes rather than a URL. Absent from
+    /// scenes persisted before schema v2, hence the default for those rows.
+    #[serde(default)]
+    model_mesh: Option<u64>,
+}
 
-                            // TODO: Apply texture from texture_url (pending API)
-                            if !node.texture_url.is_empty() {
-                                eprintln!("[bridge/reify] Node {} has texture URL: {} - NOT YET APPLIED (API limitation)",
-                                    id, node.texture_url);
-                            }
-                            Some(model.build())
-                        }
-                        Err(e) => {
-                            eprintln!("[bridge/reify] Failed to load model for node {}: {}", id, e);
-                            None
-                        }
+/// Selects the scene persistence backend, overriding the default (`SceneStore`, SQLite, at
+/// `SceneStore::default_path`). `kind` is `0` for SQLite (`path` is the `.sqlite` file) or `1`
+/// for a plain JSON file (`path` is the `.json` file); `path` is ignored and the default path
+/// used if null. Must be called before `sdxr_start`/`sdxr_start_ex` -- `scene_store()` is first
+/// read by `initial_state_update` during startup, so a call after that point is too late and
+/// returns `-1`, same as a second call after the backend is already set.
+#[no_mangle]
+pub extern "C" fn sdxr_set_persistence_backend(kind: u8, path: *const std::os::raw::c_char) -> i32 {
+    if STARTED.load(Ordering::SeqCst) {
+        return -1;
+    }
+    let path = if path.is_null() { None } else { Some(unsafe { CStr::from_ptr(path) }.to_string_lossy().to_string()) };
+    let backend: Arc<dyn PersistenceBackend> = match kind {
+        0 => {
+            let path = path.map(PathBuf::from).unwrap_or_else(SceneStore::default_path);
+            match SceneStore::open(&path) {
+                Ok(store) => Arc::new(store),
+                Err(e) => {
+                    eprintln!("[bridge] sdxr_set_persistence_backend: failed to open {}: {}", path.display(), e);
+                    return -2;
+                }
+            }
+        }
+        1 => {
+            let path = path.map(PathBuf::from).unwrap_or_else(|| SceneStore::default_path().with_extension("json"));
+            Arc::new(persistence::JsonFileStore::open(&path))
+        }
+        _ => return -3,
+    };
+    SCENE_STORE.set(backend).map(|_| 0).unwrap_or(-1)
+}
+
+/// Starts the bridge with a default-sized runtime and a 6-second connect timeout, matching
+/// the old polling loop's budget. See `sdxr_start_ex` for embedders that want to size these.
+#[no_mangle]
+pub extern "C" fn sdxr_start() -> i32 {
+    sdxr_start_ex(0, 6000)
+}
+
+/// Starts the bridge with a runtime sized to `worker_threads` (0 picks Tokio's own default,
+/// the number of CPUs) and bounds the initial compositor connect attempt to
+/// `connect_timeout_ms`, reporting the real outcome instead of assuming success after a fixed
+/// wait.
+#[no_mangle]
+pub extern "C" fn sdxr_start_ex(worker_threads: u32, connect_timeout_ms: u64) -> i32 {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return 0; // already running
+    }
+
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all();
+    if worker_threads > 0 {
+        builder.worker_threads(worker_threads as usize);
+    }
+    let rt = match builder.build() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("[bridge] Failed to build Tokio runtime: {}", e);
+            STARTED.store(false, Ordering::SeqCst);
+            return -1;
+        }
+    };
+    let rt_handle = rt.handle().clone();
+
+    let mut ctrl = CTRL.lock().unwrap();
     ctrl.next_id = 1;
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Command>();
     ctrl.tx = Some(tx.clone());
@@ -262,18 +430,79 @@ struct Node {
     let shared_for_commands = Arc::clone(&shared_state);
     let shared_for_event_loop = Arc::clone(&shared_state);
 
-    // Build a multi-threaded Tokio runtime for the client
-    let rt = tokio::runtime::Builder::new_multi_thread()
-        .enable_all()
-        .build()
-        .expect("tokio runtime");
+    // Per-node last-applied Lamport clock, consulted by the command task below so a stale
+    // replicated edit can never clobber a newer one (ours or a peer's).
+    let applied_lamport = Arc::new(Mutex::new(HashMap::<u64, u64>::new()));
+    ctrl.applied_lamport = Some(Arc::clone(&applied_lamport));
+
+    // Peer-to-peer replication: pairs with other bridge instances on the LAN and forwards
+    // their Command stream into `tx` so it's applied through this same handler.
+    let peers = networking::PeerNetwork::new(networking::Identity::load_or_create(), tx.clone(), rt_handle.clone());
+    ctrl.peers = Some(Arc::clone(&peers));
+
+    // Dirty-scene channel: mutating commands push the latest node snapshot here and
+    // `persist_task` flushes it to disk, keeping SQLite writes off the frame loop.
+    let (persist_tx, mut persist_rx) = tokio::sync::mpsc::unbounded_channel::<HashMap<u64, Node>>();
+    ctrl.persist_tx = Some(persist_tx.clone());
+
+    // Cancelled on shutdown to signal the event loop and command task directly instead of
+    // having them poll a flag.
+    let cancel = CancellationToken::new();
+    ctrl.cancel = Some(cancel.clone());
+
+    // Reports the real outcome of the initial compositor connect attempt; `sdxr_start_ex`
+    // blocks on this instead of polling a pair of atomics for up to a fixed timeout.
+    let (connect_tx, connect_rx) = tokio::sync::oneshot::channel::<ConnectResult>();
+
+    ctrl.rt = Some(rt);
     let handle = std::thread::spawn(move || {
-        let res = rt.block_on(async move {
+        let res = rt_handle.block_on(async move {
+            // Flushes the dirty-scene channel to disk. Coalesces bursts of edits into a
+            // single write by draining down to whatever snapshot is latest once one arrives.
+            let persist_task = tokio::spawn(async move {
+                while let Some(mut nodes) = persist_rx.recv().await {
+                    while let Ok(newer) = persist_rx.try_recv() {
+                        nodes = newer;
+                    }
+                    if let Err(e) = scene_store().save(&nodes) {
+                        eprintln!("[bridge] Failed to flush persisted scene: {}", e);
+                    }
+                }
+            });
+
             // Spawn command processor task that updates shared state
+            let persist_tx_for_cmds = persist_tx.clone();
+            let peers_for_cmds = Arc::clone(&peers);
+            let cmd_cancel = cancel.clone();
             let cmd_task = tokio::spawn(async move {
+                // Last-writer-wins: apply `cmd` only if its Lamport value is newer than
+                // whatever was last applied for that node (local or replicated).
+                fn accepts(applied: &Mutex<HashMap<u64, u64>>, c_id: u64, lamport: u64) -> bool {
+                    let mut applied = applied.lock().unwrap();
+                    let newer = applied.get(&c_id).map_or(true, |&last| lamport > last);
+                    if newer {
+                        applied.insert(c_id, lamport);
+                    }
+                    newer
+                }
+
                 while let Some(cmd) = rx.recv().await {
+                    if let (Some(c_id), Some(lamport)) = (cmd.c_id(), cmd.lamport()) {
+                        if !accepts(&applied_lamport, c_id, lamport) {
+                            println!("[bridge] dropping stale command for node id={} (lamport={})", c_id, lamport);
+                            continue;
+                        }
+                    }
+                    // Forwarded even if `c_id` doesn't name a node this instance knows about
+                    // yet (e.g. a peer further along than us) -- `replication::broadcast` reads
+                    // whatever it can from `shared_for_commands` to skip redundant sends, but
+                    // doesn't require a hit.
+                    replication::broadcast(&cmd, &shared_for_commands);
+                    // Also forwarded to any Noise-paired peers from `sdxr_connect_peer`/
+                    // discovery, independent of the `sdxr_net_host`/`sdxr_net_connect` hub above.
+                    peers_for_cmds.broadcast(&cmd);
                     match cmd {
-                        Command::Create { c_id, name, transform } => {
+                        Command::Create { c_id, name, transform, .. } => {
                             if let Ok(mut state) = shared_for_commands.lock() {
                                 let node = Node {
                                     id: c_id,
@@ -287,98 +516,137 @@ struct Node {
                                 };
                                 state.nodes.insert(c_id, node);
                                 println!("[bridge] create node id={} name={} (state nodes={})", c_id, name, state.nodes.len());
+                                let _ = persist_tx_for_cmds.send(state.nodes.clone());
+                                events::emit(Event::NodeChanged { c_id });
                             }
                         }
-                        Command::Update { c_id, transform } => {
+                        Command::Update { c_id, transform, .. } => {
                             if let Ok(mut state) = shared_for_commands.lock() {
                                 if let Some(n) = state.nodes.get_mut(&c_id) {
                                     n.transform = transform;
                                     // Suppress verbose per-frame update logs; enable for debugging if needed
                                     // println!("[bridge] update node id={}", c_id);
+                                    let _ = persist_tx_for_cmds.send(state.nodes.clone());
+                                    events::emit(Event::NodeChanged { c_id });
                                 } else {
                                     println!("[bridge] update for unknown node id={}", c_id);
                                 }
                             }
                         }
-                        Command::SetModel { c_id, model_url } => {
+                        Command::SetModel { c_id, model_url, .. } => {
                             if let Ok(mut state) = shared_for_commands.lock() {
                                 if let Some(n) = state.nodes.get_mut(&c_id) {
                                     n.model_url = model_url.clone();
                                     println!("[bridge] set model for node id={}: {}", c_id, model_url);
+                                    let _ = persist_tx_for_cmds.send(state.nodes.clone());
+                                    events::emit(Event::NodeChanged { c_id });
                                 }
                             }
                         }
-                        Command::SetTexture { c_id, texture_url } => {
+                        Command::SetModelMesh { c_id, mesh_handle, .. } => {
+                            if let Ok(mut state) = shared_for_commands.lock() {
+                                if let Some(n) = state.nodes.get_mut(&c_id) {
+                                    n.model_mesh = Some(mesh_handle);
+                                    println!("[bridge] set model mesh for node id={}: handle={:#x}", c_id, mesh_handle);
+                                    let _ = persist_tx_for_cmds.send(state.nodes.clone());
+                                    events::emit(Event::NodeChanged { c_id });
+                                }
+                            }
+                        }
+                        Command::SetTexture { c_id, texture_url, .. } => {
                             if let Ok(mut state) = shared_for_commands.lock() {
                                 if let Some(n) = state.nodes.get_mut(&c_id) {
                                     n.texture_url = texture_url.clone();
                                     println!("[bridge] set texture for node id={}: {}", c_id, texture_url);
+                                    let _ = persist_tx_for_cmds.send(state.nodes.clone());
+                                    events::emit(Event::NodeChanged { c_id });
                                 }
                             }
                         }
-                        Command::SetColor { c_id, color } => {
+                        Command::SetColor { c_id, color, .. } => {
                             if let Ok(mut state) = shared_for_commands.lock() {
                                 if let Some(n) = state.nodes.get_mut(&c_id) {
                                     n.color = color;
                                     println!("[bridge] set color for node id={}: {:?}", c_id, color);
+                                    let _ = persist_tx_for_cmds.send(state.nodes.clone());
+                                    events::emit(Event::NodeChanged { c_id });
                                 }
                             }
                         }
-                        Command::SetDimensions { c_id, dimensions } => {
+                        Command::SetDimensions { c_id, dimensions, .. } => {
                             if let Ok(mut state) = shared_for_commands.lock() {
                                 if let Some(n) = state.nodes.get_mut(&c_id) {
                                     n.dimensions = dimensions;
                                     println!("[bridge] set dimensions for node id={}: {:?}", c_id, dimensions);
+                                    let _ = persist_tx_for_cmds.send(state.nodes.clone());
+                                    events::emit(Event::NodeChanged { c_id });
                                 }
                             }
                         }
-                        Command::SetEntityType { c_id, entity_type } => {
+                        Command::SetEntityType { c_id, entity_type, .. } => {
                             if let Ok(mut state) = shared_for_commands.lock() {
                                 if let Some(n) = state.nodes.get_mut(&c_id) {
                                     n.entity_type = entity_type;
                                     println!("[bridge] set entity type for node id={}: {}", c_id, entity_type);
+                                    let _ = persist_tx_for_cmds.send(state.nodes.clone());
+                                    events::emit(Event::NodeChanged { c_id });
                                 }
                             }
                         }
-                        Command::Remove { c_id } => {
+                        Command::Remove { c_id, .. } => {
                             if let Ok(mut state) = shared_for_commands.lock() {
                                 if state.nodes.remove(&c_id).is_some() {
                                     println!("[bridge] remove node id={} (remaining={})", c_id, state.nodes.len());
+                                    let _ = persist_tx_for_cmds.send(state.nodes.clone());
+                                    events::emit(Event::NodeChanged { c_id });
                                 }
                             }
                         }
-                        Command::Shutdown => { STOP_REQUESTED.store(true, Ordering::SeqCst); break; }
+                        Command::Shutdown => { cmd_cancel.cancel(); break; }
                     }
                 }
             });
             println!("[bridge] Connecting to Stardust server...");
-            // Retry fusion connect with a timeout to detect missing compositor
-            let max_retries = 10; // 5 seconds total (10 * 500ms)
-            let mut retry_count = 0;
-            let mut client = loop {
-                match stardust_xr_fusion::client::Client::connect().await {
-                    Ok(c) => {
-                        println!("[bridge] Successfully connected to Stardust compositor");
-                        CONNECTION_SUCCESS.store(true, Ordering::SeqCst);
-                        break c;
-                    }
-                    Err(e) => {
-                        retry_count += 1;
-                        if retry_count >= max_retries {
-                            eprintln!("[bridge] ERROR: Could not connect to Stardust compositor after {} attempts", max_retries);
-                            eprintln!("[bridge] ERROR: {:?}", e);
-                            eprintln!("[bridge] Make sure the Stardust server is running:");
-                            eprintln!("[bridge]   systemctl --user start stardust");
-                            eprintln!("[bridge]   or: stardust-xr-server");
-                            CONNECTION_FAILED.store(true, Ordering::SeqCst);
-                            return; // Exit the async block, which will cause sdxr_start to return error
+            // Retry fusion connect, bounded by `connect_timeout_ms` rather than a fixed
+            // attempt count, and reporting the real outcome through `connect_tx` instead of
+            // a pair of atomics the caller has to poll.
+            let connect_cancel = cancel.clone();
+            let connect_attempt = async move {
+                let mut attempt = 0u32;
+                loop {
+                    attempt += 1;
+                    match stardust_xr_fusion::client::Client::connect().await {
+                        Ok(c) => return Some(c),
+                        Err(e) => {
+                            eprintln!("[bridge] Fusion connect failed (attempt {}): {:?}; retrying...", attempt, e);
+                            if connect_cancel.is_cancelled() { return None; }
+                            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
                         }
-                        eprintln!("[bridge] Fusion connect failed (attempt {}/{}): {:?}; retrying...", retry_count, max_retries, e);
-                        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-                        if STOP_REQUESTED.load(Ordering::SeqCst) { return; }
                     }
                 }
             };
+            let mut client = match tokio::time::timeout(
+                std::time::Duration::from_millis(connect_timeout_ms),
+                connect_attempt,
+            ).await {
+                Ok(Some(c)) => {
+                    println!("[bridge] Successfully connected to Stardust compositor");
+                    let _ = connect_tx.send(ConnectResult::Success);
+                    c
+                }
+                Ok(None) => {
+                    let _ = connect_tx.send(ConnectResult::Failed("shutdown requested before connecting".into()));
+                    return;
+                }
+                Err(_) => {
+                    eprintln!("[bridge] ERROR: Could not connect to Stardust compositor within {}ms", connect_timeout_ms);
+                    eprintln!("[bridge] Make sure the Stardust server is running:");
+                    eprintln!("[bridge]   systemctl --user start stardust");
+                    eprintln!("[bridge]   or: stardust-xr-server");
+                    let _ = connect_tx.send(ConnectResult::TimedOut);
+                    return;
+                }
+            };
             let dbus_connection = match fusion_connect_client().await {
                 Ok(c) => c,
                 Err(e) => {
@@ -411,6 +679,8 @@ struct Node {
             };
             
             println!("[bridge] Persistent event loop running");
+            let persist_tx_for_events = persist_tx.clone();
+            let event_cancel = cancel.clone();
             let event_loop_fut = client.sync_event_loop(|client, flow| {
                 use stardust_xr_fusion::root::{RootEvent, ClientState as SaveStatePayload};
                 let mut frames = vec![];
@@ -421,7 +691,16 @@ struct Node {
                         }
                         RootEvent::Frame { info } => frames.push(info),
                         RootEvent::SaveState { response } => {
-                            let payload = SaveStatePayload { data: None, root: client.get_root().id(), spatial_anchors: Default::default() };
+                            // Don't serialize on this thread: hand the current nodes to
+                            // `persist_task` so the actual disk write happens off the event loop.
+                            if let Ok(state) = shared_for_event_loop.lock() {
+                                let _ = persist_tx_for_events.send(state.nodes.clone());
+                            }
+                            let payload = SaveStatePayload {
+                                data: Some(persistence::SCHEMA_VERSION.to_le_bytes().to_vec()),
+                                root: client.get_root().id(),
+                                spatial_anchors: Default::default(),
+                            };
                             let _ = response.send_ok(payload);
                         }
                     }
@@ -438,57 +717,90 @@ struct Node {
                     projector.update(&context, &mut *state);
                 }
                 
-                if STOP_REQUESTED.load(Ordering::SeqCst) { flow.stop(); }
+                if event_cancel.is_cancelled() { flow.stop(); }
             });
             if let Err(e) = event_loop_fut.await {
                 eprintln!("[bridge] Event loop error: {:?}", e);
+                events::emit(Event::ConnectionLost);
             }
             println!("[bridge] Event loop terminated");
             let _ = cmd_task;
+            let _ = persist_task;
         });
-        drop(rt);
         let _ = res;
         STARTED.store(false, Ordering::SeqCst);
     });
 
-    ctrl.rt = None; // runtime consumed inside thread
     ctrl.handle = Some(handle);
     // Store the shared state so we can read from it later
     ctrl.shared_state = Some(shared_state);
-    
-    STOP_REQUESTED.store(false, Ordering::SeqCst);
-    
-    // Wait for connection to succeed or fail (max 6 seconds)
-    let max_wait_iterations = 120; // 120 * 50ms = 6 seconds
-    for _ in 0..max_wait_iterations {
-        if CONNECTION_SUCCESS.load(Ordering::SeqCst) {
+    drop(ctrl);
+
+    // Block on the real connect outcome instead of polling a pair of atomics for a fixed
+    // window; the connect task itself is the one bounded by `connect_timeout_ms`.
+    match connect_rx.blocking_recv() {
+        Ok(ConnectResult::Success) => {
             println!("[bridge] Connection established successfully");
-            return 0; // Success
+            0
         }
-        if CONNECTION_FAILED.load(Ordering::SeqCst) {
-            eprintln!("[bridge] Connection failed - exiting");
-            STARTED.store(false, Ordering::SeqCst);
-            return -1; // Failure
+        Ok(ConnectResult::Failed(reason)) => {
+            eprintln!("[bridge] Connection failed: {}", reason);
+            sdxr_shutdown_ex(1000);
+            -1
+        }
+        Ok(ConnectResult::TimedOut) => {
+            eprintln!("[bridge] Connection attempt timed out after {}ms", connect_timeout_ms);
+            sdxr_shutdown_ex(1000);
+            -1
+        }
+        Err(_) => {
+            eprintln!("[bridge] Connect task ended without reporting a result");
+            sdxr_shutdown_ex(1000);
+            -1
         }
-        std::thread::sleep(std::time::Duration::from_millis(50));
     }
-    
-    eprintln!("[bridge] WARNING: Connection status unknown after timeout");
-    0 // Assume success to maintain backwards compatibility if status isn't set
 }
 
 #[no_mangle]
 pub extern "C" fn sdxr_poll() -> i32 { if !STARTED.load(Ordering::SeqCst) { -1 } else { 0 } }
 
+/// Shuts the bridge down, draining in-flight commands and the event loop within 5 seconds.
 #[no_mangle]
 pub extern "C" fn sdxr_shutdown() {
+    sdxr_shutdown_ex(5000);
+}
+
+/// Like `sdxr_shutdown`, but lets the caller bound how long to wait for in-flight commands
+/// and the event loop to drain before the runtime's worker threads are forcibly torn down --
+/// this call can never hang the host application past `drain_timeout_ms`.
+#[no_mangle]
+pub extern "C" fn sdxr_shutdown_ex(drain_timeout_ms: u64) {
     let mut ctrl = CTRL.lock().unwrap();
-    if let Some(tx) = ctrl.tx.take() {
+    let cancel = ctrl.cancel.take();
+    let tx = ctrl.tx.take();
+    let handle = ctrl.handle.take();
+    let rt = ctrl.rt.take();
+    drop(ctrl);
+
+    // Signal the event loop and command task directly rather than letting them discover a
+    // stale flag on their next poll.
+    if let Some(cancel) = &cancel {
+        cancel.cancel();
+    }
+    if let Some(tx) = tx {
         let _ = tx.send(Command::Shutdown);
     }
-    if let Some(h) = ctrl.handle.take() {
-        let _ = h.join();
+
+    if let Some(rt) = rt {
+        // Forces any remaining tasks (the command/persist/event-loop tasks, and the
+        // `block_on` driving them on `handle`) to wrap up within the bound instead of
+        // letting a wedged task hang the shutdown call indefinitely.
+        rt.shutdown_timeout(std::time::Duration::from_millis(drain_timeout_ms));
     }
+    if let Some(handle) = handle {
+        let _ = handle.join();
+    }
+
     STARTED.store(false, Ordering::SeqCst);
 }
 
@@ -503,7 +815,117 @@ pub extern "C" fn sdxr_create_node(name: *const std::os::raw::c_char, mat4: *con
 
     let mut ctrl = CTRL.lock().unwrap();
     let c_id = ctrl.next_id; ctrl.next_id += 1;
-    if let Some(tx) = &ctrl.tx { let _ = tx.send(Command::Create { c_id, name, transform: mat }); }
+    let lamport = ctrl.tick();
+    if let Some(tx) = &ctrl.tx { let _ = tx.send(Command::Create { c_id, name, transform: mat, lamport }); }
+    c_id
+}
+
+/// Configures the blueprint library `sdxr_spawn_blueprint` resolves names against: `folder` is
+/// `BlueprintsConfig::library_folder` and `format` selects the container (`0` = `.glb`, `1` =
+/// `.gltf`). Replaces any previously configured library. Built-in blueprints (`"cube"`,
+/// `"sphere"`) are always spawnable even with no library configured.
+#[no_mangle]
+pub extern "C" fn sdxr_set_blueprint_library(folder: *const std::os::raw::c_char, format: u8) -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    if folder.is_null() { return -2; }
+    let folder = unsafe { CStr::from_ptr(folder) }.to_string_lossy().to_string();
+    let format = match format {
+        0 => blueprints::BlueprintFormat::Glb,
+        1 => blueprints::BlueprintFormat::Gltf,
+        _ => return -3,
+    };
+    let config = blueprints::BlueprintsConfig { library_folder: PathBuf::from(folder), format, compute_aabb: true };
+    let mut ctrl = CTRL.lock().unwrap();
+    ctrl.blueprints = Some(Arc::new(blueprints::BlueprintLibrary::new(config)));
+    0
+}
+
+/// Spawns a node from a named blueprint: resolves `name` through the library configured by
+/// `sdxr_set_blueprint_library` (falling back to `BlueprintLibrary::builtin` if no library is
+/// configured or `name` isn't found in it), parses its scene graph through `gltf_loader`
+/// (`.glb` via the same `load_and_cache` path `sdxr_set_node_model_bytes` uses; `.gltf` via
+/// `load_gltf_file`, which resolves external buffer/image URIs through the library's
+/// `UriResolver`), and merges any `<name>.components.json` sidecar onto the new node via the
+/// matching `Set*` commands. Returns the new node's id, or `0` if `name` can't be resolved or
+/// parsed.
+#[no_mangle]
+pub extern "C" fn sdxr_spawn_blueprint(name: *const std::os::raw::c_char, mat4: *const f32) -> u64 {
+    if !STARTED.load(Ordering::SeqCst) { return 0; }
+    if name.is_null() { return 0; }
+    let name = unsafe { CStr::from_ptr(name) }.to_string_lossy().to_string();
+    let m = unsafe { std::slice::from_raw_parts(mat4, 16) };
+    let mut arr = [0.0f32; 16];
+    arr.copy_from_slice(m);
+    let transform = Mat4::from_cols_array(&arr);
+
+    let (mesh_handle, overrides) = {
+        let ctrl = CTRL.lock().unwrap();
+        match ctrl.blueprints.as_ref().and_then(|lib| lib.get(&name).map(|bp| (Arc::clone(lib), bp))) {
+            Some((lib, blueprint)) => {
+                let overrides = blueprint.component_overrides().unwrap_or_default();
+                let handle = match lib.format() {
+                    blueprints::BlueprintFormat::Glb => std::fs::read(&blueprint.path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|bytes| gltf_loader::load_and_cache(&bytes).map_err(|e| format!("{:?}", e))),
+                    blueprints::BlueprintFormat::Gltf => std::fs::read(&blueprint.path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|raw| {
+                            gltf_loader::load_gltf_file(&blueprint.path, lib.resolver().as_ref())
+                                .map(|mesh| gltf_loader::cache_parsed(mesh, &raw))
+                                .map_err(|e| format!("{:?}", e))
+                        }),
+                };
+                match handle {
+                    Ok(handle) => (handle, overrides),
+                    Err(e) => {
+                        eprintln!("[bridge] sdxr_spawn_blueprint: failed to parse '{}': {}", name, e);
+                        return 0;
+                    }
+                }
+            }
+            None => match blueprints::BlueprintLibrary::builtin(&name) {
+                Some(path) => match std::fs::read(&path).map_err(|e| e.to_string()).and_then(|bytes| {
+                    gltf_loader::load_and_cache(&bytes).map_err(|e| format!("{:?}", e))
+                }) {
+                    Ok(handle) => (handle, blueprints::ComponentOverrides::default()),
+                    Err(e) => {
+                        eprintln!("[bridge] sdxr_spawn_blueprint: failed to read built-in '{}': {}", name, e);
+                        return 0;
+                    }
+                },
+                None => {
+                    eprintln!("[bridge] sdxr_spawn_blueprint: no blueprint named '{}'", name);
+                    return 0;
+                }
+            },
+        }
+    };
+
+    let mut ctrl = CTRL.lock().unwrap();
+    let c_id = ctrl.next_id; ctrl.next_id += 1;
+    let Some(tx) = ctrl.tx.clone() else { return 0 };
+
+    let lamport = ctrl.tick();
+    let _ = tx.send(Command::Create { c_id, name: name.clone(), transform, lamport });
+    let lamport = ctrl.tick();
+    let _ = tx.send(Command::SetModelMesh { c_id, mesh_handle, lamport });
+    if let Some(entity_type) = overrides.entity_type {
+        let lamport = ctrl.tick();
+        let _ = tx.send(Command::SetEntityType { c_id, entity_type, lamport });
+    }
+    if let Some(color) = overrides.color {
+        let lamport = ctrl.tick();
+        let _ = tx.send(Command::SetColor { c_id, color, lamport });
+    }
+    if let Some(dimensions) = overrides.dimensions {
+        let lamport = ctrl.tick();
+        let _ = tx.send(Command::SetDimensions { c_id, dimensions, lamport });
+    }
+    if let Some(texture_url) = overrides.texture_url {
+        let lamport = ctrl.tick();
+        let _ = tx.send(Command::SetTexture { c_id, texture_url, lamport });
+    }
+
     c_id
 }
 
@@ -514,16 +936,18 @@ pub extern "C" fn sdxr_update_node(id: u64, mat4: *const f32) -> i32 {
     let mut arr = [0.0f32; 16];
     arr.copy_from_slice(m);
     let mat = Mat4::from_cols_array(&arr);
-    let ctrl = CTRL.lock().unwrap();
-    if let Some(tx) = &ctrl.tx { let _ = tx.send(Command::Update { c_id: id, transform: mat }); }
+    let mut ctrl = CTRL.lock().unwrap();
+    let lamport = ctrl.tick();
+    if let Some(tx) = &ctrl.tx { let _ = tx.send(Command::Update { c_id: id, transform: mat, lamport }); }
     0
 }
 
 #[no_mangle]
 pub extern "C" fn sdxr_remove_node(id: u64) -> i32 {
     if !STARTED.load(Ordering::SeqCst) { return -1; }
-    let ctrl = CTRL.lock().unwrap();
-    if let Some(tx) = &ctrl.tx { let _ = tx.send(Command::Remove { c_id: id }); }
+    let mut ctrl = CTRL.lock().unwrap();
+    let lamport = ctrl.tick();
+    if let Some(tx) = &ctrl.tx { let _ = tx.send(Command::Remove { c_id: id, lamport }); }
     0
 }
 
@@ -539,9 +963,42 @@ pub extern "C" fn sdxr_node_count() -> u64 {
 pub extern "C" fn sdxr_set_node_model(id: u64, model_url: *const std::os::raw::c_char) -> i32 {
     if !STARTED.load(Ordering::SeqCst) { return -1; }
     let url = unsafe { CStr::from_ptr(model_url) }.to_string_lossy().to_string();
-    let ctrl = CTRL.lock().unwrap();
+    let mut ctrl = CTRL.lock().unwrap();
+    let lamport = ctrl.tick();
     if let Some(tx) = &ctrl.tx {
-        let _ = tx.send(Command::SetModel { c_id: id, model_url: url });
+        let _ = tx.send(Command::SetModel { c_id: id, model_url: url, lamport });
+    }
+    0
+}
+
+/// Like `sdxr_set_node_model`, but takes `len` bytes of an already-in-memory `.glb` at `ptr`
+/// instead of a URL the engine has to download and guess the format of. `format` selects the
+/// container: `0` is GLB (the only one currently supported). On success the parsed mesh is
+/// cached content-addressed and `Command::SetModelMesh` carries its handle. Returns `0` on
+/// success, or a negative code: `-1` bridge not started, `-2` null `ptr`, `-3` unsupported
+/// `format`, or one of `gltf_loader::GltfError::code()`'s values (`-3` truncated buffer, `-4`
+/// bad magic, `-5` unsupported GLB version, `-6` missing JSON chunk, `-7` invalid JSON, `-8`
+/// missing a required accessor, `-9` an unsupported accessor/component type, `-10` I/O error).
+#[no_mangle]
+pub extern "C" fn sdxr_set_node_model_bytes(id: u64, ptr: *const u8, len: usize, format: u8) -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    if ptr.is_null() { return -2; }
+    if format != 0 {
+        return -3;
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+    let mesh_handle = match gltf_loader::load_and_cache(bytes) {
+        Ok(handle) => handle,
+        Err(e) => {
+            eprintln!("[bridge] sdxr_set_node_model_bytes: failed to parse .glb for node {}: {:?}", id, e);
+            return e.code();
+        }
+    };
+
+    let mut ctrl = CTRL.lock().unwrap();
+    let lamport = ctrl.tick();
+    if let Some(tx) = &ctrl.tx {
+        let _ = tx.send(Command::SetModelMesh { c_id: id, mesh_handle, lamport });
     }
     0
 }
@@ -550,9 +1007,10 @@ pub extern "C" fn sdxr_set_node_model(id: u64, model_url: *const std::os::raw::c
 pub extern "C" fn sdxr_set_node_texture(id: u64, texture_url: *const std::os::raw::c_char) -> i32 {
     if !STARTED.load(Ordering::SeqCst) { return -1; }
     let url = unsafe { CStr::from_ptr(texture_url) }.to_string_lossy().to_string();
-    let ctrl = CTRL.lock().unwrap();
+    let mut ctrl = CTRL.lock().unwrap();
+    let lamport = ctrl.tick();
     if let Some(tx) = &ctrl.tx {
-        let _ = tx.send(Command::SetTexture { c_id: id, texture_url: url });
+        let _ = tx.send(Command::SetTexture { c_id: id, texture_url: url, lamport });
     }
     0
 }
@@ -560,9 +1018,10 @@ pub extern "C" fn sdxr_set_node_texture(id: u64, texture_url: *const std::os::ra
 #[no_mangle]
 pub extern "C" fn sdxr_set_node_color(id: u64, r: f32, g: f32, b: f32, a: f32) -> i32 {
     if !STARTED.load(Ordering::SeqCst) { return -1; }
-    let ctrl = CTRL.lock().unwrap();
+    let mut ctrl = CTRL.lock().unwrap();
+    let lamport = ctrl.tick();
     if let Some(tx) = &ctrl.tx {
-        let _ = tx.send(Command::SetColor { c_id: id, color: [r, g, b, a] });
+        let _ = tx.send(Command::SetColor { c_id: id, color: [r, g, b, a], lamport });
     }
     0
 }
@@ -570,9 +1029,10 @@ pub extern "C" fn sdxr_set_node_color(id: u64, r: f32, g: f32, b: f32, a: f32) -
 #[no_mangle]
 pub extern "C" fn sdxr_set_node_dimensions(id: u64, x: f32, y: f32, z: f32) -> i32 {
     if !STARTED.load(Ordering::SeqCst) { return -1; }
-    let ctrl = CTRL.lock().unwrap();
+    let mut ctrl = CTRL.lock().unwrap();
+    let lamport = ctrl.tick();
     if let Some(tx) = &ctrl.tx {
-        let _ = tx.send(Command::SetDimensions { c_id: id, dimensions: [x, y, z] });
+        let _ = tx.send(Command::SetDimensions { c_id: id, dimensions: [x, y, z], lamport });
     }
     0
 }
@@ -580,9 +1040,394 @@ pub extern "C" fn sdxr_set_node_dimensions(id: u64, x: f32, y: f32, z: f32) -> i
 #[no_mangle]
 pub extern "C" fn sdxr_set_node_entity_type(id: u64, entity_type: u8) -> i32 {
     if !STARTED.load(Ordering::SeqCst) { return -1; }
-    let ctrl = CTRL.lock().unwrap();
+    let mut ctrl = CTRL.lock().unwrap();
+    let lamport = ctrl.tick();
     if let Some(tx) = &ctrl.tx {
-        let _ = tx.send(Command::SetEntityType { c_id: id, entity_type });
+        let _ = tx.send(Command::SetEntityType { c_id: id, entity_type, lamport });
+    }
+    0
+}
+
+/// Opcodes for `sdxr_apply_commands`' packed buffer, one per fixed-size `Command` variant it
+/// can construct. `Create`/`SetModel`/`SetTexture` carry a variable-length string payload and
+/// are deliberately left out -- they're one-off calls, not the per-frame transform deltas this
+/// batch path exists to amortize -- so hosts still reach them through their own `sdxr_*` setters.
+#[repr(u8)]
+enum BatchOp {
+    Update = 0,
+    SetColor = 1,
+    SetDimensions = 2,
+    SetEntityType = 3,
+    Remove = 4,
+}
+
+/// A decoded batch record, still missing the Lamport stamp `sdxr_apply_commands` assigns once
+/// it holds the `CTRL` lock.
+enum BatchedCommand {
+    Update { c_id: u64, transform: Mat4 },
+    SetColor { c_id: u64, color: [f32; 4] },
+    SetDimensions { c_id: u64, dimensions: [f32; 3] },
+    SetEntityType { c_id: u64, entity_type: u8 },
+    Remove { c_id: u64 },
+}
+
+impl BatchedCommand {
+    fn into_command(self, lamport: u64) -> Command {
+        match self {
+            BatchedCommand::Update { c_id, transform } => Command::Update { c_id, transform, lamport },
+            BatchedCommand::SetColor { c_id, color } => Command::SetColor { c_id, color, lamport },
+            BatchedCommand::SetDimensions { c_id, dimensions } => Command::SetDimensions { c_id, dimensions, lamport },
+            BatchedCommand::SetEntityType { c_id, entity_type } => Command::SetEntityType { c_id, entity_type, lamport },
+            BatchedCommand::Remove { c_id } => Command::Remove { c_id, lamport },
+        }
+    }
+}
+
+fn read_u8(buf: &[u8], pos: &mut usize) -> Result<u8, ()> {
+    let byte = *buf.get(*pos).ok_or(())?;
+    *pos += 1;
+    Ok(byte)
+}
+
+fn read_u64(buf: &[u8], pos: &mut usize) -> Result<u64, ()> {
+    let end = pos.checked_add(8).ok_or(())?;
+    let bytes: [u8; 8] = buf.get(*pos..end).ok_or(())?.try_into().unwrap();
+    *pos = end;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f32(buf: &[u8], pos: &mut usize) -> Result<f32, ()> {
+    let end = pos.checked_add(4).ok_or(())?;
+    let bytes: [u8; 4] = buf.get(*pos..end).ok_or(())?.try_into().unwrap();
+    *pos = end;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+/// Decodes `sdxr_apply_commands`' packed buffer: a little-endian `u32` record count followed
+/// by that many tagged records (`1` byte [`BatchOp`] opcode, `u64` node id, then the opcode's
+/// fixed payload). Fails closed on any unknown opcode or truncated record rather than applying
+/// a partially-decoded batch.
+fn decode_batch(buf: &[u8]) -> Result<Vec<BatchedCommand>, ()> {
+    let mut pos = 0usize;
+    let count_bytes: [u8; 4] = buf.get(0..4).ok_or(())?.try_into().unwrap();
+    let count = u32::from_le_bytes(count_bytes) as usize;
+    pos += 4;
+
+    let mut commands = Vec::with_capacity(count);
+    for _ in 0..count {
+        let opcode = read_u8(buf, &mut pos)?;
+        let c_id = read_u64(buf, &mut pos)?;
+        let command = if opcode == BatchOp::Update as u8 {
+            let mut cols = [0.0f32; 16];
+            for v in cols.iter_mut() {
+                *v = read_f32(buf, &mut pos)?;
+            }
+            BatchedCommand::Update { c_id, transform: Mat4::from_cols_array(&cols) }
+        } else if opcode == BatchOp::SetColor as u8 {
+            let mut color = [0.0f32; 4];
+            for v in color.iter_mut() {
+                *v = read_f32(buf, &mut pos)?;
+            }
+            BatchedCommand::SetColor { c_id, color }
+        } else if opcode == BatchOp::SetDimensions as u8 {
+            let mut dimensions = [0.0f32; 3];
+            for v in dimensions.iter_mut() {
+                *v = read_f32(buf, &mut pos)?;
+            }
+            BatchedCommand::SetDimensions { c_id, dimensions }
+        } else if opcode == BatchOp::SetEntityType as u8 {
+            BatchedCommand::SetEntityType { c_id, entity_type: read_u8(buf, &mut pos)? }
+        } else if opcode == BatchOp::Remove as u8 {
+            BatchedCommand::Remove { c_id }
+        } else {
+            return Err(());
+        };
+        commands.push(command);
+    }
+    Ok(commands)
+}
+
+/// Applies a packed buffer of node commands in a single `CTRL` lock and channel drain, instead
+/// of the per-call lock/send that `sdxr_update_node` and friends pay -- for hosts (e.g. a
+/// voxel/Bevy-style renderer) that need to stream a whole frame's worth of transform deltas
+/// across the FFI boundary in one crossing. See [`decode_batch`] for the buffer layout. Returns
+/// the number of commands applied, or a negative code if the bridge isn't started, `ptr` is
+/// null, or the buffer is malformed/truncated.
+#[no_mangle]
+pub extern "C" fn sdxr_apply_commands(ptr: *const u8, len: usize) -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    if ptr.is_null() { return -2; }
+    let buf = unsafe { std::slice::from_raw_parts(ptr, len) };
+    let commands = match decode_batch(buf) {
+        Ok(commands) => commands,
+        Err(()) => return -3,
+    };
+
+    let mut ctrl = CTRL.lock().unwrap();
+    let Some(tx) = ctrl.tx.clone() else { return -1 };
+    let mut applied = 0i32;
+    for batched in commands {
+        let lamport = ctrl.tick();
+        if tx.send(batched.into_command(lamport)).is_ok() {
+            applied += 1;
+        }
+    }
+    applied
+}
+
+#[no_mangle]
+pub extern "C" fn sdxr_connect_peer(addr: *const std::os::raw::c_char) -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    let addr = unsafe { CStr::from_ptr(addr) }.to_string_lossy().to_string();
+    let ctrl = CTRL.lock().unwrap();
+    match (&ctrl.peers, addr.parse()) {
+        (Some(peers), Ok(addr)) => {
+            peers.connect(addr);
+            0
+        }
+        (None, _) => -1,
+        (_, Err(_)) => -2, // malformed socket address
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn sdxr_list_peers(out: *mut u64, cap: usize) -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    let ctrl = CTRL.lock().unwrap();
+    let Some(peers) = &ctrl.peers else { return -1 };
+    let ids = peers.list();
+    if cap >= ids.len() {
+        let out = unsafe { std::slice::from_raw_parts_mut(out, ids.len()) };
+        out.copy_from_slice(&ids);
+    }
+    ids.len() as i32
+}
+
+#[no_mangle]
+pub extern "C" fn sdxr_enable_discovery(enabled: bool) -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    let ctrl = CTRL.lock().unwrap();
+    let Some(peers) = &ctrl.peers else { return -1 };
+    peers.set_discovery_enabled(enabled);
+    0
+}
+
+/// Starts accepting `sdxr_net_connect` clients on `port`, replicating this instance's `Command`
+/// stream to each one (and theirs back into this instance's) over a plain, unpaired TCP
+/// transport. Unlike `sdxr_connect_peer`'s Noise-encrypted LAN pairing, this is meant for a
+/// known, already-trusted set of clients sharing one scene -- e.g. several headsets joining the
+/// same hosted session.
+#[no_mangle]
+pub extern "C" fn sdxr_net_host(port: u16) -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    let ctrl = CTRL.lock().unwrap();
+    let Some(tx) = &ctrl.tx else { return -1 };
+    match replication::start_host(tx, port) {
+        Ok(()) => 0,
+        Err(e) => { eprintln!("[bridge] sdxr_net_host: failed to bind port {}: {}", port, e); -2 }
     }
+}
+
+/// Connects to a replication host at `addr` ("host:port") started with `sdxr_net_host`.
+#[no_mangle]
+pub extern "C" fn sdxr_net_connect(addr: *const std::os::raw::c_char) -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    if addr.is_null() { return -2; }
+    let addr = unsafe { CStr::from_ptr(addr) }.to_string_lossy().to_string();
+    let ctrl = CTRL.lock().unwrap();
+    let Some(tx) = &ctrl.tx else { return -1 };
+    replication::start_client(tx, addr);
     0
 }
+
+/// Applies every command decoded off the replication wire since the last call, the same way a
+/// local FFI call would. Hosts should call this once per frame alongside `sdxr_poll_event`.
+/// Returns the number applied (`0` if replication was never started).
+#[no_mangle]
+pub extern "C" fn sdxr_net_poll() -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    replication::poll()
+}
+
+/// Loads `path` as a WASM plugin module (see `plugins`) and returns a handle for
+/// `sdxr_plugin_tick`/`sdxr_unload_plugin`, or a negative code if the file can't be read or the
+/// module fails to instantiate.
+#[no_mangle]
+pub extern "C" fn sdxr_load_plugin(path: *const std::os::raw::c_char) -> i64 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    if path.is_null() { return -2; }
+    let path = unsafe { CStr::from_ptr(path) }.to_string_lossy().to_string();
+    match std::fs::read(&path) {
+        Ok(bytes) => sdxr_load_plugin_bytes(bytes.as_ptr(), bytes.len()),
+        Err(e) => { eprintln!("[bridge] sdxr_load_plugin: failed to read {}: {}", path, e); -3 }
+    }
+}
+
+/// Like `sdxr_load_plugin`, but for a WASM module already loaded into memory.
+#[no_mangle]
+pub extern "C" fn sdxr_load_plugin_bytes(ptr: *const u8, len: usize) -> i64 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    if ptr.is_null() { return -2; }
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+    let tx = { let ctrl = CTRL.lock().unwrap(); ctrl.tx.clone() };
+    let Some(tx) = tx else { return -1 };
+    match plugins::load(bytes, tx) {
+        Ok(handle) => handle as i64,
+        Err(e) => { eprintln!("[bridge] sdxr_load_plugin_bytes: failed to instantiate module: {}", e); -4 }
+    }
+}
+
+/// Invokes `handle`'s exported `tick(dt)` for one frame. Returns `0` on success, or a negative
+/// code if `handle` is unknown or the module trapped.
+#[no_mangle]
+pub extern "C" fn sdxr_plugin_tick(handle: u64, dt: f32) -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    plugins::tick(handle, dt)
+}
+
+/// Unloads `handle`, `Remove`-ing every node it created.
+#[no_mangle]
+pub extern "C" fn sdxr_unload_plugin(handle: u64) -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    plugins::unload(handle)
+}
+
+/// Explicitly snapshots the current scene to `path`, independent of the background
+/// `persist_task` flush and the default scene store location.
+#[no_mangle]
+pub extern "C" fn sdxr_save_scene(path: *const std::os::raw::c_char) -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    let path = unsafe { CStr::from_ptr(path) }.to_string_lossy().to_string();
+    let ctrl = CTRL.lock().unwrap();
+    let Some(shared) = &ctrl.shared_state else { return -1 };
+    let nodes = match shared.lock() {
+        Ok(state) => state.nodes.clone(),
+        Err(_) => return -1,
+    };
+    match SceneStore::open(Path::new(&path)) {
+        Ok(store) => match store.save(&nodes) {
+            Ok(()) => 0,
+            Err(e) => { eprintln!("[bridge] sdxr_save_scene failed: {}", e); -2 }
+        },
+        Err(e) => { eprintln!("[bridge] sdxr_save_scene: failed to open {}: {}", path, e); -3 }
+    }
+}
+
+/// Replaces the running scene with the snapshot stored at `path`.
+#[no_mangle]
+pub extern "C" fn sdxr_load_scene(path: *const std::os::raw::c_char) -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    let path = unsafe { CStr::from_ptr(path) }.to_string_lossy().to_string();
+    let ctrl = CTRL.lock().unwrap();
+    let Some(shared) = &ctrl.shared_state else { return -1 };
+    let store = match SceneStore::open(Path::new(&path)) {
+        Ok(store) => store,
+        Err(e) => { eprintln!("[bridge] sdxr_load_scene: failed to open {}: {}", path, e); return -3; }
+    };
+    match store.load() {
+        Ok(snapshot) => {
+            if let Ok(mut state) = shared.lock() {
+                state.nodes = snapshot.nodes;
+            }
+            0
+        }
+        Err(e) => { eprintln!("[bridge] sdxr_load_scene failed: {}", e); -2 }
+    }
+}
+
+/// Encodes the current scene into `scene_io`'s compact binary format and writes it to
+/// `out_ptr`, a buffer of `cap` bytes the caller owns. Unlike `sdxr_save_scene` this never
+/// touches disk -- the blob is meant for a host-owned save slot or a network transfer.
+///
+/// Follows the classic two-call FFI sizing pattern: call once with `cap = 0` (`out_ptr` may be
+/// null) to learn the required size from the return value, then again with a big-enough buffer
+/// to actually fill it. Returns the blob size on success, which may be larger than `cap` if the
+/// buffer was too small -- nothing is written to `out_ptr` in that case.
+#[no_mangle]
+pub extern "C" fn sdxr_export_scene(out_ptr: *mut u8, cap: usize) -> i64 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    let ctrl = CTRL.lock().unwrap();
+    let Some(shared) = &ctrl.shared_state else { return -1 };
+    let nodes = match shared.lock() {
+        Ok(state) => state.nodes.clone(),
+        Err(_) => return -1,
+    };
+
+    let bytes = scene_io::encode(&nodes);
+    if cap < bytes.len() {
+        return bytes.len() as i64;
+    }
+    if !out_ptr.is_null() {
+        let out = unsafe { std::slice::from_raw_parts_mut(out_ptr, bytes.len()) };
+        out.copy_from_slice(&bytes);
+    }
+    bytes.len() as i64
+}
+
+/// Rebuilds the scene from a blob produced by `sdxr_export_scene`, replaying a `Create` plus
+/// whichever `Set*` commands each record needs through the same `tx` local FFI calls use -- so
+/// imported nodes persist, replicate, and emit `NodeChanged` exactly like any other mutation.
+/// Returns the number of nodes imported, or a negative error code.
+#[no_mangle]
+pub extern "C" fn sdxr_import_scene(ptr: *const u8, len: usize) -> i32 {
+    if !STARTED.load(Ordering::SeqCst) { return -1; }
+    if ptr.is_null() { return -2; }
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+    let nodes = match scene_io::decode(bytes) {
+        Ok(nodes) => nodes,
+        Err(()) => return -3,
+    };
+
+    let mut ctrl = CTRL.lock().unwrap();
+    let Some(tx) = ctrl.tx.clone() else { return -1 };
+    let count = nodes.len();
+    for node in &nodes {
+        if node.id >= ctrl.next_id {
+            ctrl.next_id = node.id + 1;
+        }
+    }
+
+    for node in nodes {
+        let lamport = ctrl.tick();
+        let _ = tx.send(Command::Create { c_id: node.id, name: node.name, transform: node.transform, lamport });
+
+        let lamport = ctrl.tick();
+        let _ = tx.send(Command::SetEntityType { c_id: node.id, entity_type: node.entity_type, lamport });
+
+        if !node.model_url.is_empty() {
+            let lamport = ctrl.tick();
+            let _ = tx.send(Command::SetModel { c_id: node.id, model_url: node.model_url, lamport });
+        }
+        if let Some(mesh_handle) = node.model_mesh {
+            let lamport = ctrl.tick();
+            let _ = tx.send(Command::SetModelMesh { c_id: node.id, mesh_handle, lamport });
+        }
+        if !node.texture_url.is_empty() {
+            let lamport = ctrl.tick();
+            let _ = tx.send(Command::SetTexture { c_id: node.id, texture_url: node.texture_url, lamport });
+        }
+
+        let lamport = ctrl.tick();
+        let _ = tx.send(Command::SetColor { c_id: node.id, color: node.color, lamport });
+
+        let lamport = ctrl.tick();
+        let _ = tx.send(Command::SetDimensions { c_id: node.id, dimensions: node.dimensions, lamport });
+    }
+    count as i32
+}
+
+/// Registers `callback` to receive every [`events::EventRecord`] as it's emitted, from
+/// whichever thread emits it (the download, command-handler, or event-loop task). Pass `None`
+/// to unregister and switch back to `sdxr_poll_event`-based delivery.
+#[no_mangle]
+pub extern "C" fn sdxr_set_event_callback(callback: Option<extern "C" fn(*const events::EventRecord)>) {
+    events::set_callback(callback);
+}
+
+/// Pops the oldest queued event into `*out`, returning 1, or 0 if none is queued. Only
+/// queues events while no callback is registered via `sdxr_set_event_callback`.
+#[no_mangle]
+pub extern "C" fn sdxr_poll_event(out: *mut events::EventRecord) -> i32 {
+    if out.is_null() { return -1; }
+    events::poll(out)
+}
@@ -1,177 +1,899 @@
-// Model downloader for fetching GLTF/GLB models from URLs
+// Model downloader for fetching GLTF/GLB models from URLs, content-addressed by SHA-256 so
+// the same asset served from two different URLs is only ever downloaded and stored once, and
+// a truncated or corrupted download can never be served as if it were complete.
+//
+// Cache keys (both the `.partial` temp name and the final file) are already full-URL/content
+// SHA-256 digests rather than a truncated, collision-prone reversal of the URL, and an
+// expected digest carried in the URL (`#sha256=<hex>`) is already verified against the
+// downloaded bytes before the path is handed back -- see `partial_path_for` and
+// `run_download` below.
+//
+// Downloads run as spawned tokio tasks rather than blocking the calling thread: `poll_model`
+// kicks one off and returns immediately, and every concurrent caller for the same URL shares
+// the one in-flight task (see `InFlight`) instead of racing to start duplicates -- the old
+// blocking `get_model` remains as a thin synchronous wrapper over the same machinery for the
+// one call site (the per-frame `reify`) that hasn't been converted to poll yet.
+//
+// A dropped connection no longer discards progress on a large model: `fetch_to_partial` HEAD-
+// probes for size and range support, and resumes a leftover `.partial` file with a `Range`
+// request rather than restarting, falling back to a clean download if the server doesn't play
+// along (see `run_download`/`fetch_to_partial`).
+//
+// Gated hosts are supported via `AuthTokens`, a host(+port)-keyed table of bearer/basic
+// credentials attached to every outgoing request (see `apply_auth`); any credentials embedded
+// in the URL itself are stripped before the URL ever reaches `eprintln!` (see
+// `redact_url_for_log`).
+//
+// The cache is bounded, not unbounded: `set_max_bytes` caps total on-disk size, and a fresh
+// download past that cap evicts least-recently-used entries first (see
+// `evict_if_over_budget`), skipping any digest whose URL has a download or revalidation
+// currently in flight. Sizes and access times are reconstructed from disk on startup (see
+// `scan_cache_dir`) so the limit is enforced correctly even on the first download after a
+// restart.
 
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
-use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Computes a simple hash of a URL for cache filenames
-fn url_hash(url: &str) -> String {
-    // Simple hash: use last path component + length as identifier
-    // In production, use a proper hash like SHA256
-    let sanitized = url.replace(['/', ':', '?', '&', '='], "_");
-    let len = url.len();
-    format!("{:x}_{}", len, sanitized.chars().rev().take(32).collect::<String>())
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+
+use crate::events::{self, Event};
+
+/// How a [`ModelDownloader`] should treat a URL that already has a cached, digest-addressed
+/// file on disk.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CacheSetting {
+    /// Never touch the network for a URL that's already cached, even past its stored max-age.
+    UseOnly,
+    /// Serve the cached file immediately, kicking off a background revalidation (conditional
+    /// GET) once its stored `Cache-Control: max-age` has elapsed. The default.
+    Revalidate,
+    /// Ignore anything cached and always perform a full, unconditional re-download.
+    ReloadAll,
+}
+
+/// State of a model as seen by a non-blocking caller (e.g. the render loop), returned by
+/// [`ModelDownloader::poll_model`].
+pub enum ModelState {
+    Ready(PathBuf),
+    /// `0.0..=1.0` once `Content-Length` is known, `0.0` while it isn't.
+    Downloading(f32),
+    Failed,
+}
+
+/// Called with `(bytes_downloaded, total_bytes)` as a download streams in; `total_bytes` is
+/// `None` until (if ever) the response carries a `Content-Length`.
+pub type ProgressCallback = Arc<dyn Fn(u64, Option<u64>) + Send + Sync>;
+
+/// Freshness metadata captured from a download's response headers, persisted as
+/// `<digest>.meta.json` next to the cached file so a later run can revalidate without
+/// re-downloading.
+#[derive(Default, Clone, serde::Serialize, serde::Deserialize)]
+struct CacheMeta {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    max_age_secs: Option<u64>,
+    fetched_at_unix: u64,
 }
 
-/// Model cache entry
+impl CacheMeta {
+    fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        let header_str = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        Self {
+            etag: header_str("etag"),
+            last_modified: header_str("last-modified"),
+            max_age_secs: headers.get("cache-control").and_then(|v| v.to_str().ok()).and_then(parse_max_age),
+            fetched_at_unix: now_unix(),
+        }
+    }
+}
+
+/// A credential attached to outgoing requests for a gated host: either a bearer token or a
+/// basic-auth username/password pair.
 #[derive(Clone)]
+pub enum Credential {
+    Bearer(String),
+    Basic { username: String, password: String },
+}
+
+/// Per-host credentials for fetching models from hosts that sit behind auth. Matched by the
+/// request URL's host (optionally `host:port`, for a host serving both a public and a gated
+/// port); a URL whose host has no entry is requested with no `Authorization` header at all.
+#[derive(Clone, Default)]
+pub struct AuthTokens {
+    by_host: HashMap<String, Credential>,
+}
+
+impl AuthTokens {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, host: impl Into<String>, credential: Credential) {
+        self.by_host.insert(host.into(), credential);
+    }
+
+    /// Parses `STARWORLD_MODEL_AUTH_TOKENS`-style config: `token@host;user:pass@host2`. A
+    /// token containing `:` is treated as `username:password` (basic auth); anything else is a
+    /// bearer token. Malformed entries are skipped with a warning rather than failing startup.
+    pub fn from_env_value(value: &str) -> Self {
+        let mut tokens = Self::new();
+        for entry in value.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.rsplit_once('@') {
+                Some((cred, host)) => {
+                    let credential = match cred.split_once(':') {
+                        Some((user, pass)) => Credential::Basic { username: user.to_string(), password: pass.to_string() },
+                        None => Credential::Bearer(cred.to_string()),
+                    };
+                    tokens.insert(host.to_string(), credential);
+                }
+                None => eprintln!("[downloader] Ignoring malformed auth token entry (expected `token@host`)"),
+            }
+        }
+        tokens
+    }
+
+    /// Reads and parses `var_name` from the environment, if set.
+    pub fn from_env(var_name: &str) -> Self {
+        std::env::var(var_name).map(|v| Self::from_env_value(&v)).unwrap_or_default()
+    }
+
+    fn for_url(&self, url: &str) -> Option<&Credential> {
+        let parsed = reqwest::Url::parse(url).ok()?;
+        let host = parsed.host_str()?;
+        if let Some(port) = parsed.port() {
+            if let Some(cred) = self.by_host.get(&format!("{}:{}", host, port)) {
+                return Some(cred);
+            }
+        }
+        self.by_host.get(host)
+    }
+}
+
+/// Attaches this host's registered credential (if any) to `request`.
+fn apply_auth(request: reqwest::RequestBuilder, auth_tokens: &AuthTokens, url: &str) -> reqwest::RequestBuilder {
+    match auth_tokens.for_url(url) {
+        Some(Credential::Bearer(token)) => request.bearer_auth(token),
+        Some(Credential::Basic { username, password }) => request.basic_auth(username, Some(password)),
+        None => request,
+    }
+}
+
+/// Strips embedded userinfo (`user:pass@`) from a URL before it's ever handed to `eprintln!`,
+/// so a credential baked into the URL itself (as opposed to one registered via
+/// [`AuthTokens`]) never ends up in logs.
+fn redact_url_for_log(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(mut parsed) if !parsed.username().is_empty() || parsed.password().is_some() => {
+            let _ = parsed.set_username("");
+            let _ = parsed.set_password(None);
+            parsed.to_string()
+        }
+        _ => url.to_string(),
+    }
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Pulls `max-age=<seconds>` out of a `Cache-Control` header value that may carry other
+/// comma-separated directives (`no-cache`, `must-revalidate`, ...) alongside it.
+fn parse_max_age(value: &str) -> Option<u64> {
+    value.split(',').find_map(|part| part.trim().strip_prefix("max-age=")?.parse().ok())
+}
+
+/// Splits an optional `#sha256=<hex>` integrity fragment off `url`, lower-casing the digest.
+/// The fragment never reaches the HTTP request -- only the base URL is fetched.
+fn split_expected_digest(url: &str) -> (&str, Option<String>) {
+    match url.split_once('#') {
+        Some((base, frag)) if frag.starts_with("sha256=") => {
+            (base, Some(frag["sha256=".len()..].to_lowercase()))
+        }
+        _ => (url, None),
+    }
+}
+
+fn hex_digest(hasher: Sha256) -> String {
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn extension_for(url: &str) -> &'static str {
+    if url.ends_with(".glb") {
+        "glb"
+    } else if url.ends_with(".gltf") {
+        "gltf"
+    } else if url.ends_with(".vrm") {
+        "vrm"
+    } else {
+        eprintln!("[downloader] Unknown extension for {}, assuming .glb", redact_url_for_log(url));
+        "glb"
+    }
+}
+
+fn index_path(cache_dir: &Path) -> PathBuf {
+    cache_dir.join("url_index.json")
+}
+
+fn load_index(path: &Path) -> HashMap<String, String> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn load_meta(path: &Path) -> Option<CacheMeta> {
+    fs::read_to_string(path).ok().and_then(|s| serde_json::from_str(&s).ok())
+}
+
+/// One in-flight download or revalidation, shared by every caller requesting the same URL
+/// concurrently so only one task actually touches the network; a second `poll_model` call
+/// for the same URL reads this instead of spawning a duplicate.
+struct InFlight {
+    progress: Mutex<f32>,
+    result: Mutex<Option<Result<PathBuf, ()>>>,
+    notify: tokio::sync::Notify,
+}
+
+impl InFlight {
+    fn new() -> Arc<Self> {
+        Arc::new(Self { progress: Mutex::new(0.0), result: Mutex::new(None), notify: tokio::sync::Notify::new() })
+    }
+
+    fn set_progress(&self, downloaded: u64, total: Option<u64>) {
+        if let Some(total) = total.filter(|t| *t > 0) {
+            *self.progress.lock().unwrap() = (downloaded as f32 / total as f32).min(1.0);
+        }
+    }
+
+    fn finish(&self, result: Result<PathBuf, ()>) {
+        *self.result.lock().unwrap() = Some(result);
+        self.notify.notify_waiters();
+    }
+}
+
+/// Model cache entry, keyed by content digest rather than URL. Tracks size and last-access
+/// time so [`ModelDownloader::evict_if_over_budget`] can find the least-recently-used entries.
 struct CacheEntry {
     path: PathBuf,
-    downloading: bool,
+    size: u64,
+    last_access: AtomicU64,
 }
 
-/// Downloads and caches 3D models from HTTP URLs
+impl CacheEntry {
+    fn touch(&self) {
+        self.last_access.store(now_unix(), Ordering::SeqCst);
+    }
+}
+
+/// Rebuilds the cache map from whatever digest-addressed files are already sitting in
+/// `cache_dir` (skipping `.partial` downloads-in-progress, `.meta.json` sidecars, and the url
+/// index), so size/LRU accounting is correct from the very first download after a restart.
+/// Last-access is seeded from the file's recorded access time (falling back to its mtime, then
+/// to now) since there's no better signal for how recently a pre-restart entry was used.
+fn scan_cache_dir(cache_dir: &Path) -> HashMap<String, CacheEntry> {
+    let mut cache = HashMap::new();
+    let Ok(entries) = fs::read_dir(cache_dir) else { return cache };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+        if file_name.ends_with(".partial") || file_name.ends_with(".meta.json") || file_name == "url_index.json" {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else { continue };
+        if !metadata.is_file() {
+            continue;
+        }
+        let Some(digest) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+        let last_access = metadata
+            .accessed()
+            .or_else(|_| metadata.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or_else(now_unix);
+        cache.insert(
+            digest.to_string(),
+            CacheEntry { path, size: metadata.len(), last_access: AtomicU64::new(last_access) },
+        );
+    }
+    cache
+}
+
+/// Downloads and caches 3D models from HTTP URLs.
 pub struct ModelDownloader {
     cache_dir: PathBuf,
+    /// digest -> cache entry.
     cache: Arc<Mutex<HashMap<String, CacheEntry>>>,
-    client: reqwest::blocking::Client,
+    /// url -> digest, persisted as JSON next to the cache so repeat requests for the same
+    /// URL skip the network but still resolve to the deduplicated blob.
+    url_index: Arc<Mutex<HashMap<String, String>>>,
+    /// base url -> the single in-flight download/revalidation task for it, if any.
+    downloads: Arc<Mutex<HashMap<String, Arc<InFlight>>>>,
+    /// base urls currently being revalidated in the background, so a stale cache hit doesn't
+    /// spawn a second revalidation while one is already running.
+    revalidating: Arc<Mutex<HashSet<String>>>,
+    client: reqwest::Client,
+    cache_setting: CacheSetting,
+    runtime: tokio::runtime::Handle,
+    auth_tokens: AuthTokens,
+    /// Soft cap on the cache's total on-disk size; `u64::MAX` (the default) means unbounded.
+    /// See [`set_max_bytes`](Self::set_max_bytes).
+    max_bytes: Mutex<u64>,
 }
 
 impl ModelDownloader {
-    /// Create a new model downloader with the given cache directory
-    pub fn new(cache_dir: PathBuf) -> Result<Self, std::io::Error> {
+    /// Create a new model downloader with the given cache directory, cache policy, and
+    /// per-host credentials for gated hosts. Downloads are spawned onto `runtime` rather than
+    /// run on the caller's thread.
+    pub fn new(
+        cache_dir: PathBuf,
+        cache_setting: CacheSetting,
+        runtime: tokio::runtime::Handle,
+        auth_tokens: AuthTokens,
+    ) -> Result<Self, std::io::Error> {
         fs::create_dir_all(&cache_dir)?;
-        
-        let client = reqwest::blocking::Client::builder()
+
+        // A leftover `.partial` file from a previous run (or a dropped connection earlier
+        // this run) is no longer discarded here -- `fetch_to_partial` resumes it with a
+        // `Range` request the next time its URL is requested, rather than losing the bytes
+        // already on disk. A partial that turns out to be corrupt is still caught and removed
+        // at finalize time, by the size/digest checks in `run_download`.
+        let client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-        
+
         Ok(Self {
+            url_index: Arc::new(Mutex::new(load_index(&index_path(&cache_dir)))),
+            cache: Arc::new(Mutex::new(scan_cache_dir(&cache_dir))),
             cache_dir,
-            cache: Arc::new(Mutex::new(HashMap::new())),
+            downloads: Arc::new(Mutex::new(HashMap::new())),
+            revalidating: Arc::new(Mutex::new(HashSet::new())),
             client,
+            cache_setting,
+            runtime,
+            auth_tokens,
+            max_bytes: Mutex::new(u64::MAX),
         })
     }
-    
-    /// Get a model from URL, downloading if necessary
-    /// Returns PathBuf if available, None if downloading or failed
-    pub fn get_model(&self, url: &str) -> Option<PathBuf> {
-        // Check cache first
-        {
-            let cache = self.cache.lock().ok()?;
-            if let Some(entry) = cache.get(url) {
-                if entry.downloading {
-                    eprintln!("[downloader] Model still downloading: {}", url);
-                    return None;
+
+    /// Sets the maximum total on-disk cache size in bytes, evicting least-recently-used
+    /// entries immediately if the cache is already over the new limit. `u64::MAX` (the
+    /// default) disables the limit entirely.
+    pub fn set_max_bytes(&self, max_bytes: u64) {
+        *self.max_bytes.lock().unwrap() = max_bytes;
+        self.evict_if_over_budget("");
+    }
+
+    /// Returns `(entry_count, total_bytes)` for the current on-disk cache.
+    pub fn cache_stats(&self) -> (usize, u64) {
+        let cache = self.cache.lock().unwrap();
+        (cache.len(), cache.values().map(|e| e.size).sum())
+    }
+
+    /// Synchronous convenience wrapper over [`poll_model`](Self::poll_model) for callers (the
+    /// per-frame `reify` path) that haven't been converted to poll yet. Never blocks on the
+    /// network itself -- it just reports whatever `poll_model` already knows, same as before,
+    /// except a download no longer gets stuck: the spawned task keeps making progress between
+    /// calls regardless of whether anyone's polling it.
+    pub fn get_model(self: &'static Self, c_id: u64, url: &str) -> Option<PathBuf> {
+        let on_progress: ProgressCallback = Arc::new(move |bytes, total| {
+            events::emit(Event::ModelDownloadProgress { c_id, bytes, total: total.unwrap_or(0) });
+        });
+        match self.poll_model(c_id, url, Some(on_progress)) {
+            ModelState::Ready(path) => Some(path),
+            ModelState::Downloading(_) | ModelState::Failed => None,
+        }
+    }
+
+    /// Non-blocking: returns the model's current state, kicking off a background download (or
+    /// revalidation of a stale cache hit) the first time a URL is seen. `on_progress`, if
+    /// given, is only used the first time a *new* download for `url` is started -- a later
+    /// `poll_model` call for the same in-flight URL reads its progress directly instead.
+    pub fn poll_model(self: &'static Self, c_id: u64, url: &str, on_progress: Option<ProgressCallback>) -> ModelState {
+        let (base_url, expected_digest) = split_expected_digest(url);
+
+        if !matches!(self.cache_setting, CacheSetting::ReloadAll) {
+            if let Some(path) = self.try_cached(base_url, expected_digest.as_deref()) {
+                return ModelState::Ready(path);
+            }
+        }
+
+        let existing = self.downloads.lock().unwrap().get(base_url).cloned();
+        if let Some(inflight) = existing {
+            if let Some(result) = inflight.result.lock().unwrap().clone() {
+                self.downloads.lock().unwrap().remove(base_url);
+                return match result {
+                    Ok(path) => {
+                        events::emit(Event::ModelReady { c_id });
+                        ModelState::Ready(path)
+                    }
+                    Err(()) => {
+                        events::emit(Event::ModelFailed { c_id, code: -1 });
+                        ModelState::Failed
+                    }
+                };
+            }
+            return ModelState::Downloading(*inflight.progress.lock().unwrap());
+        }
+
+        let inflight = InFlight::new();
+        self.downloads.lock().unwrap().insert(base_url.to_string(), inflight.clone());
+
+        let base_url = base_url.to_string();
+        self.runtime.spawn(async move {
+            let result = self.run_download(c_id, &base_url, expected_digest.as_deref(), &inflight, on_progress).await;
+            inflight.finish(result);
+        });
+
+        ModelState::Downloading(0.0)
+    }
+
+    /// Awaits `url`'s in-flight download (starting one via [`poll_model`](Self::poll_model) if
+    /// none is running) instead of polling it -- for async callers that can afford to wait
+    /// rather than check back next frame.
+    pub async fn wait_for_model(self: &'static Self, c_id: u64, url: &str) -> Option<PathBuf> {
+        loop {
+            let inflight = {
+                match self.poll_model(c_id, url, None) {
+                    ModelState::Ready(path) => return Some(path),
+                    ModelState::Failed => return None,
+                    ModelState::Downloading(_) => {}
                 }
-                if entry.path.exists() {
-                    return Some(entry.path.clone());
+                self.downloads.lock().unwrap().get(split_expected_digest(url).0).cloned()
+            };
+            match inflight {
+                Some(inflight) => inflight.notify.notified().await,
+                // Another caller already collected the result between the two checks above;
+                // the next `poll_model` will report it.
+                None => return self.get_model(c_id, url),
+            }
+        }
+    }
+
+    /// Cache hit fast path: resolves `base_url` (or a pinned `expected_digest`) to an already
+    /// downloaded file without touching the network, kicking off a background revalidation if
+    /// the hit is stale. Returns `None` on a miss, leaving the caller to start a real download.
+    fn try_cached(self: &'static Self, base_url: &str, expected_digest: Option<&str>) -> Option<PathBuf> {
+        if let Some(digest) = self.url_index.lock().ok()?.get(base_url).cloned() {
+            if expected_digest.is_none_or_eq(&digest) {
+                if let Some(path) = self.cached_path(&digest) {
+                    // A digest pin is proof of content; nothing to revalidate against the
+                    // server for that case.
+                    if expected_digest.is_none()
+                        && matches!(self.cache_setting, CacheSetting::Revalidate)
+                        && self.is_stale(&load_meta(&self.meta_path(&digest)))
+                    {
+                        self.maybe_spawn_revalidate(base_url.to_string(), digest);
+                    }
+                    return Some(path);
                 }
+            } else {
+                eprintln!(
+                    "[downloader] Indexed digest for {} does not match requested digest, re-fetching",
+                    redact_url_for_log(base_url)
+                );
             }
         }
-        
-        // Determine file extension from URL
-        let extension = if url.ends_with(".glb") {
-            "glb"
-        } else if url.ends_with(".gltf") {
-            "gltf"
-        } else if url.ends_with(".vrm") {
-            "vrm"
-        } else {
-            // Default to GLB
-            eprintln!("[downloader] Unknown extension for {}, assuming .glb", url);
-            "glb"
+
+        if let Some(expected) = expected_digest {
+            if let Some(path) = self.cached_path(expected) {
+                self.url_index.lock().ok()?.insert(base_url.to_string(), expected.to_string());
+                self.persist_index();
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    fn is_stale(&self, meta: &Option<CacheMeta>) -> bool {
+        let Some(meta) = meta else { return true };
+        let Some(max_age) = meta.max_age_secs else { return true };
+        now_unix().saturating_sub(meta.fetched_at_unix) >= max_age
+    }
+
+    fn maybe_spawn_revalidate(self: &'static Self, url: String, digest: String) {
+        if !self.revalidating.lock().unwrap().insert(url.clone()) {
+            return; // already revalidating this url in the background
+        }
+        self.runtime.spawn(async move {
+            let meta = load_meta(&self.meta_path(&digest));
+            let _ = self.revalidate(&url, &digest, meta.as_ref()).await;
+            self.revalidating.lock().unwrap().remove(&url);
+        });
+    }
+
+    /// Issues a conditional GET for `url` using `meta`'s stored validators. A `304` refreshes
+    /// the stored freshness timestamp and keeps the existing file; a `200` means the content
+    /// changed and is stored under its new digest, same as a fresh download.
+    async fn revalidate(&self, url: &str, digest: &str, meta: Option<&CacheMeta>) -> Option<PathBuf> {
+        let log_url = redact_url_for_log(url);
+        let mut request = apply_auth(self.client.get(url), &self.auth_tokens, url);
+        if let Some(meta) = meta {
+            if let Some(etag) = &meta.etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &meta.last_modified {
+                request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                eprintln!("[downloader] Revalidation request for {} failed: {}", log_url, e);
+                return None;
+            }
         };
-        
-        let hash = url_hash(url);
-        let filename = format!("{}.{}", hash, extension);
-        let dest_path = self.cache_dir.join(&filename);
-        
-        // Check if already on disk
-        if dest_path.exists() {
-            eprintln!("[downloader] Found cached model: {}", dest_path.display());
-            let mut cache = self.cache.lock().ok()?;
-            cache.insert(url.to_string(), CacheEntry {
-                path: dest_path.clone(),
-                downloading: false,
-            });
-            return Some(dest_path);
-        }
-        
-        // Mark as downloading
-        {
-            let mut cache = self.cache.lock().ok()?;
-            cache.insert(url.to_string(), CacheEntry {
-                path: dest_path.clone(),
-                downloading: true,
-            });
-        }
-        
-        eprintln!("[downloader] Downloading model: {}", url);
-        
-        // Download in current thread (blocking)
-        match self.download_file(url, &dest_path) {
-            Ok(_) => {
-                eprintln!("[downloader] Downloaded successfully: {}", dest_path.display());
-                let mut cache = self.cache.lock().ok()?;
-                cache.insert(url.to_string(), CacheEntry {
-                    path: dest_path.clone(),
-                    downloading: false,
-                });
-                Some(dest_path)
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            eprintln!("[downloader] {} not modified, refreshing cache freshness", log_url);
+            let mut meta = meta.cloned().unwrap_or_default();
+            meta.fetched_at_unix = now_unix();
+            self.save_meta(digest, &meta);
+            return self.cached_path(digest);
+        }
+        if !response.status().is_success() {
+            eprintln!("[downloader] Revalidation GET for {} failed: HTTP {}", log_url, response.status());
+            return None;
+        }
+
+        eprintln!("[downloader] {} changed since last fetch, re-downloading", log_url);
+        let partial_path = self.partial_path_for(url);
+        let meta = self.write_full_body(response, &partial_path).await.ok()?;
+        let new_digest = self.hash_file(&partial_path).ok()?;
+        self.store_downloaded(url, &partial_path, &new_digest, &meta)
+    }
+
+    fn cached_path(&self, digest: &str) -> Option<PathBuf> {
+        if let Some(entry) = self.cache.lock().ok()?.get(digest) {
+            if entry.path.exists() {
+                entry.touch();
+                return Some(entry.path.clone());
             }
+        }
+
+        // Fall back to the disk itself: after a restart the in-memory cache is empty, but
+        // the content-addressed file from a previous run is still there.
+        for ext in ["glb", "gltf", "vrm"] {
+            let candidate = self.cache_dir.join(format!("{}.{}", digest, ext));
+            if let Ok(metadata) = fs::metadata(&candidate) {
+                let entry = CacheEntry { path: candidate.clone(), size: metadata.len(), last_access: AtomicU64::new(now_unix()) };
+                self.cache.lock().ok()?.insert(digest.to_string(), entry);
+                return Some(candidate);
+            }
+        }
+        None
+    }
+
+    fn partial_path_for(&self, url: &str) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(url.as_bytes());
+        self.cache_dir.join(format!("{}.partial", hex_digest(hasher)))
+    }
+
+    fn meta_path(&self, digest: &str) -> PathBuf {
+        self.cache_dir.join(format!("{}.meta.json", digest))
+    }
+
+    fn save_meta(&self, digest: &str, meta: &CacheMeta) {
+        match serde_json::to_string_pretty(meta) {
+            Ok(json) => {
+                if let Err(e) = fs::write(self.meta_path(digest), json) {
+                    eprintln!("[downloader] Failed to persist cache metadata for {}: {}", digest, e);
+                }
+            }
+            Err(e) => eprintln!("[downloader] Failed to serialize cache metadata for {}: {}", digest, e),
+        }
+    }
+
+    /// Runs one download attempt for `url` to completion: HEAD-probes for size and range
+    /// support, resumes a leftover `.partial` file with a `Range` request when the server
+    /// supports it, validates the assembled file's size against the HEAD-reported total, then
+    /// hashes and finalizes it. A streaming failure deliberately leaves the `.partial` file in
+    /// place (unlike a size/digest mismatch, which discards it) so the next attempt can resume
+    /// instead of starting over.
+    async fn run_download(
+        &self,
+        _c_id: u64,
+        url: &str,
+        expected_digest: Option<&str>,
+        inflight: &InFlight,
+        on_progress: Option<ProgressCallback>,
+    ) -> Result<PathBuf, ()> {
+        let partial_path = self.partial_path_for(url);
+        let log_url = redact_url_for_log(url);
+        eprintln!("[downloader] Downloading model: {}", log_url);
+        let (meta, total) = match self.fetch_to_partial(url, &partial_path, inflight, on_progress).await {
+            Ok(result) => result,
             Err(e) => {
-                eprintln!("[downloader] Failed to download {}: {}", url, e);
-                // Remove from cache on failure
-                let mut cache = self.cache.lock().ok()?;
-                cache.remove(url);
-                None
+                eprintln!("[downloader] {} download attempt failed (resumable): {}", log_url, e);
+                return Err(());
+            }
+        };
+
+        if let Some(total) = total {
+            match fs::metadata(&partial_path) {
+                Ok(md) if md.len() == total => {}
+                Ok(md) => {
+                    eprintln!(
+                        "[downloader] {}: downloaded size {} does not match expected {}; discarding",
+                        log_url, md.len(), total
+                    );
+                    let _ = fs::remove_file(&partial_path);
+                    return Err(());
+                }
+                Err(e) => {
+                    eprintln!("[downloader] {}: failed to stat downloaded file: {}", log_url, e);
+                    return Err(());
+                }
             }
         }
+
+        let digest = match self.hash_file(&partial_path) {
+            Ok(digest) => digest,
+            Err(e) => {
+                eprintln!("[downloader] {}: failed to hash downloaded file: {}", log_url, e);
+                let _ = fs::remove_file(&partial_path);
+                return Err(());
+            }
+        };
+
+        if let Some(expected) = expected_digest {
+            if digest != expected {
+                eprintln!(
+                    "[downloader] Digest mismatch for {}: expected {}, got {}; rejecting download",
+                    log_url, expected, digest
+                );
+                let _ = fs::remove_file(&partial_path);
+                return Err(());
+            }
+        }
+
+        self.store_downloaded(url, &partial_path, &digest, &meta).ok_or(())
     }
-    
-    /// Download a file from URL to destination path
-    fn download_file(&self, url: &str, dest: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-        let response = self.client.get(url).send()?;
-        
-        if !response.status().is_success() {
+
+    /// Renames `partial_path` into its final content-addressed location and records `digest`'s
+    /// metadata, in-memory cache entry, and url index, persisting the index to disk. Shared by
+    /// a fresh download and a revalidation that came back with a `200` (changed content).
+    fn store_downloaded(&self, url: &str, partial_path: &Path, digest: &str, meta: &CacheMeta) -> Option<PathBuf> {
+        let final_path = self.cache_dir.join(format!("{}.{}", digest, extension_for(url)));
+        if let Err(e) = fs::rename(partial_path, &final_path) {
+            eprintln!("[downloader] Failed to move downloaded model into place: {}", e);
+            let _ = fs::remove_file(partial_path);
+            return None;
+        }
+
+        eprintln!("[downloader] Downloaded and cached: {}", final_path.display());
+        self.save_meta(digest, meta);
+        let size = fs::metadata(&final_path).map(|m| m.len()).unwrap_or(0);
+        let entry = CacheEntry { path: final_path.clone(), size, last_access: AtomicU64::new(now_unix()) };
+        self.cache.lock().ok()?.insert(digest.to_string(), entry);
+        self.url_index.lock().ok()?.insert(url.to_string(), digest.to_string());
+        self.persist_index();
+        self.evict_if_over_budget(digest);
+        Some(final_path)
+    }
+
+    /// Digests whose URL currently has a download or revalidation in flight, and so must not
+    /// be evicted even if they're the least-recently-used entry in the cache.
+    fn protected_digests(&self) -> HashSet<String> {
+        let downloads = self.downloads.lock().unwrap();
+        let url_index = self.url_index.lock().unwrap();
+        let revalidating = self.revalidating.lock().unwrap();
+        downloads.keys().chain(revalidating.iter()).filter_map(|url| url_index.get(url).cloned()).collect()
+    }
+
+    /// Evicts least-recently-used cache entries (other than `just_written`, which is exempt
+    /// from its own eviction pass, and any [`protected_digests`](Self::protected_digests))
+    /// until the cache's total size is back under [`max_bytes`](Self::max_bytes). A no-op
+    /// while no limit has been set.
+    fn evict_if_over_budget(&self, just_written: &str) {
+        let max_bytes = *self.max_bytes.lock().unwrap();
+        if max_bytes == u64::MAX {
+            return;
+        }
+
+        let protected = self.protected_digests();
+        let mut evicted_any = false;
+        {
+            let mut cache = self.cache.lock().unwrap();
+            let mut total: u64 = cache.values().map(|e| e.size).sum();
+            if total <= max_bytes {
+                return;
+            }
+
+            let mut candidates: Vec<(String, u64, u64)> = cache
+                .iter()
+                .filter(|(digest, _)| digest.as_str() != just_written && !protected.contains(digest.as_str()))
+                .map(|(digest, entry)| (digest.clone(), entry.last_access.load(Ordering::SeqCst), entry.size))
+                .collect();
+            candidates.sort_by_key(|(_, last_access, _)| *last_access);
+
+            for (digest, _, size) in candidates {
+                if total <= max_bytes {
+                    break;
+                }
+                if let Some(entry) = cache.remove(&digest) {
+                    if let Err(e) = fs::remove_file(&entry.path) {
+                        eprintln!("[downloader] Failed to evict cache entry {}: {}", digest, e);
+                    }
+                    let _ = fs::remove_file(self.meta_path(&digest));
+                    total = total.saturating_sub(size);
+                    evicted_any = true;
+                }
+            }
+        }
+
+        if evicted_any {
+            if let Ok(mut url_index) = self.url_index.lock() {
+                let live: HashSet<String> = self.cache.lock().unwrap().keys().cloned().collect();
+                url_index.retain(|_, digest| live.contains(digest));
+            }
+            self.persist_index();
+        }
+    }
+
+    /// HEAD-probes `url` for its total size and `Accept-Ranges` support, then GETs it into
+    /// `temp_path` -- resuming from `temp_path`'s existing length with a `Range` request if the
+    /// server advertised range support and a leftover partial file is there, or starting clean
+    /// otherwise. Falls back to a clean full download if the server answers a `Range` request
+    /// with `200` instead of `206` (ignoring the range rather than honoring it). Returns the
+    /// captured freshness metadata and the total size expected once complete, if known.
+    async fn fetch_to_partial(
+        &self,
+        url: &str,
+        temp_path: &Path,
+        inflight: &InFlight,
+        on_progress: Option<ProgressCallback>,
+    ) -> Result<(CacheMeta, Option<u64>), Box<dyn std::error::Error>> {
+        let log_url = redact_url_for_log(url);
+        let head = apply_auth(self.client.head(url), &self.auth_tokens, url)
+            .send()
+            .await
+            .ok()
+            .filter(|r| r.status().is_success());
+        let head_total = head.as_ref().and_then(|r| r.content_length());
+        let accepts_ranges = head
+            .as_ref()
+            .and_then(|r| r.headers().get(reqwest::header::ACCEPT_RANGES))
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.eq_ignore_ascii_case("bytes"));
+
+        let existing_len = fs::metadata(temp_path).map(|m| m.len()).unwrap_or(0);
+        let resuming = existing_len > 0 && accepts_ranges;
+
+        let mut request = apply_auth(self.client.get(url), &self.auth_tokens, url);
+        if resuming {
+            eprintln!("[downloader] {}: resuming from byte {}", log_url, existing_len);
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing_len));
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() && response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
             return Err(format!("HTTP {}", response.status()).into());
         }
-        
-        let bytes = response.bytes()?;
-        
-        // Create temporary file first
-        let temp_path = dest.with_extension("tmp");
-        let mut file = fs::File::create(&temp_path)?;
-        file.write_all(&bytes)?;
+
+        let (mut file, mut received) = if resuming && response.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+            (fs::OpenOptions::new().append(true).open(temp_path)?, existing_len)
+        } else {
+            if resuming {
+                eprintln!("[downloader] {}: server ignored the Range request, restarting from scratch", log_url);
+            }
+            (fs::File::create(temp_path)?, 0)
+        };
+
+        let meta = CacheMeta::from_headers(response.headers());
+        let total = head_total.or_else(|| response.content_length().map(|body_len| body_len + received));
+
+        inflight.set_progress(received, total);
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            std::io::Write::write_all(&mut file, &chunk)?;
+            received += chunk.len() as u64;
+            inflight.set_progress(received, total);
+            if let Some(cb) = &on_progress {
+                cb(received, total);
+            }
+        }
         file.sync_all()?;
         drop(file);
-        
-        // Rename to final destination
-        fs::rename(&temp_path, dest)?;
-        
-        Ok(())
+
+        Ok((meta, total))
+    }
+
+    /// Writes a response body to `temp_path` from scratch (no resume, no hashing) -- used by
+    /// [`revalidate`](Self::revalidate), whose conditional GET already has a response in hand.
+    async fn write_full_body(&self, mut response: reqwest::Response, temp_path: &Path) -> Result<CacheMeta, Box<dyn std::error::Error>> {
+        let meta = CacheMeta::from_headers(response.headers());
+        let mut file = fs::File::create(temp_path)?;
+        while let Some(chunk) = response.chunk().await? {
+            std::io::Write::write_all(&mut file, &chunk)?;
+        }
+        file.sync_all()?;
+        drop(file);
+        Ok(meta)
+    }
+
+    /// Hashes a completed (possibly resumed-and-appended) file in one pass, rather than
+    /// carrying a streaming hasher's state across separate download attempts.
+    fn hash_file(&self, path: &Path) -> std::io::Result<String> {
+        let bytes = fs::read(path)?;
+        let mut hasher = Sha256::new();
+        hasher.update(&bytes);
+        Ok(hex_digest(hasher))
+    }
+
+    fn persist_index(&self) {
+        if let Ok(index) = self.url_index.lock() {
+            match serde_json::to_string_pretty(&*index) {
+                Ok(json) => {
+                    if let Err(e) = fs::write(index_path(&self.cache_dir), json) {
+                        eprintln!("[downloader] Failed to persist url index: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("[downloader] Failed to serialize url index: {}", e),
+            }
+        }
     }
-    
+
     /// Clear the download cache
     #[allow(dead_code)]
     pub fn clear_cache(&self) -> Result<(), std::io::Error> {
         let mut cache = self.cache.lock().unwrap();
         cache.clear();
-        
+        self.url_index.lock().unwrap().clear();
+
         if self.cache_dir.exists() {
             fs::remove_dir_all(&self.cache_dir)?;
             fs::create_dir_all(&self.cache_dir)?;
         }
-        
+
         Ok(())
     }
 }
 
+/// Small helper so `try_cached` reads as "the pin matches (or there's no pin)" in one line.
+trait OptionExpectedDigestExt {
+    fn is_none_or_eq(&self, other: &str) -> bool;
+}
+
+impl OptionExpectedDigestExt for Option<&str> {
+    fn is_none_or_eq(&self, other: &str) -> bool {
+        match self {
+            Some(expected) => *expected == other,
+            None => true,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
+    #[test]
+    fn strips_and_lowercases_expected_digest_fragment() {
+        let (base, digest) = split_expected_digest("https://example.com/model.glb#sha256=ABCDEF");
+        assert_eq!(base, "https://example.com/model.glb");
+        assert_eq!(digest.as_deref(), Some("abcdef"));
+    }
+
+    #[test]
+    fn urls_without_a_digest_fragment_are_unchanged() {
+        let (base, digest) = split_expected_digest("https://example.com/model.glb");
+        assert_eq!(base, "https://example.com/model.glb");
+        assert_eq!(digest, None);
+    }
+
     #[test]
-    fn test_url_hash() {
-        let hash1 = url_hash("https://example.com/model.glb");
-        let hash2 = url_hash("https://example.com/model.glb");
-        let hash3 = url_hash("https://example.com/other.glb");
-        
-        assert_eq!(hash1, hash2);
-        assert_ne!(hash1, hash3);
+    fn parses_max_age_among_other_cache_control_directives() {
+        assert_eq!(parse_max_age("max-age=600"), Some(600));
+        assert_eq!(parse_max_age("no-cache, max-age=120"), Some(120));
+        assert_eq!(parse_max_age("no-store"), None);
     }
 }
@@ -0,0 +1,439 @@
+// Peer-to-peer scene replication: pairs Starworld bridge instances on a LAN so they can
+// share one BridgeState. Paired peers exchange the same `Command` stream that drives local
+// FFI mutations, so replicated edits are applied through the exact same handler rather than
+// being re-derived on the receiving side.
+//
+// Pairing is a Noise_XX handshake (see `do_handshake`) over each side's persistent X25519
+// identity key, so the transport is encrypted and mutually authenticated without either side
+// needing to already know the other's key in advance -- the right fit for "two bridges just
+// met on a LAN". The Ed25519 identity key (used for `instance_id` and display purposes) is
+// bound to that session's Noise static key by a signature carried in the post-handshake
+// `NodeInformation` exchange (see `run_peer_session`), so a paired peer's claimed identity is
+// backed by the same key that authenticated the transport, not just self-reported.
+//
+// Discovery is real mDNS (the `mdns-sd` crate): enabling it both advertises this instance's
+// pairing port under `_starworld._tcp.local.` and browses for others, auto-`connect`ing to any
+// resolved peer that isn't already paired (see `set_discovery_enabled`).
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::replication::{decode_frame, encode_frame};
+use crate::Command;
+
+/// Fixed port this instance listens for incoming pairing connections on. Unlike
+/// `sdxr_net_host`'s port (chosen by the embedder for a hosted session), pairing is meant to
+/// just work between any two bridges on the same LAN, so there's nothing for an embedder to
+/// configure.
+const PAIRING_PORT: u16 = 53317;
+
+const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+
+const SERVICE_TYPE: &str = "_starworld._tcp.local.";
+
+/// This instance's persistent identity. Generated once on first launch and reused on every
+/// subsequent start so peers recognize the same bridge across restarts. Carries both keys a
+/// paired session needs: the Ed25519 key is this instance's long-lived, human-facing identity
+/// (`instance_id`, display name); the X25519 key is the Noise static key that actually
+/// authenticates and encrypts the transport.
+pub struct Identity {
+    pub instance_id: u64,
+    signing_key: SigningKey,
+    noise_key: x25519_dalek::StaticSecret,
+}
+
+impl Identity {
+    fn path() -> PathBuf {
+        dirs::config_dir().unwrap_or_else(|| PathBuf::from("/tmp")).join("starworld/identity.key")
+    }
+
+    /// Loads the persisted keypair, or generates and saves a new one on first run. The file is
+    /// the 32-byte Ed25519 seed followed by the 32-byte X25519 secret scalar.
+    pub fn load_or_create() -> Self {
+        let path = Self::path();
+        if let Ok(bytes) = std::fs::read(&path) {
+            if bytes.len() == 64 {
+                let seed: [u8; 32] = bytes[0..32].try_into().unwrap();
+                let noise_seed: [u8; 32] = bytes[32..64].try_into().unwrap();
+                let signing_key = SigningKey::from_bytes(&seed);
+                let noise_key = x25519_dalek::StaticSecret::from(noise_seed);
+                return Self { instance_id: instance_id_from(&signing_key.verifying_key()), signing_key, noise_key };
+            }
+            eprintln!("[networking] Identity file at {} is malformed, regenerating", path.display());
+        }
+
+        let signing_key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let noise_key = x25519_dalek::StaticSecret::random_from_rng(rand::rngs::OsRng);
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let mut bytes = Vec::with_capacity(64);
+        bytes.extend_from_slice(&signing_key.to_bytes());
+        bytes.extend_from_slice(&noise_key.to_bytes());
+        if let Err(e) = std::fs::write(&path, &bytes) {
+            eprintln!("[networking] Failed to persist identity to {}: {}", path.display(), e);
+        }
+        Self { instance_id: instance_id_from(&signing_key.verifying_key()), signing_key, noise_key }
+    }
+
+    pub fn public_key(&self) -> VerifyingKey {
+        self.signing_key.verifying_key()
+    }
+
+    fn noise_public_key(&self) -> [u8; 32] {
+        x25519_dalek::PublicKey::from(&self.noise_key).to_bytes()
+    }
+}
+
+fn instance_id_from(key: &VerifyingKey) -> u64 {
+    let bytes = key.to_bytes();
+    u64::from_le_bytes(bytes[0..8].try_into().unwrap())
+}
+
+/// Handshake exchanged (as the first two Noise transport messages) once a peer connects.
+/// `noise_key_signature` binds this session's Noise static key to `public_key` -- the thing
+/// that actually proves `instance_id`/`display_name` belong to whoever authenticated the
+/// transport, rather than being a self-reported label a peer could claim without owning the
+/// matching key.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct NodeInformation {
+    pub instance_id: u64,
+    pub public_key: [u8; 32],
+    pub display_name: String,
+    pub capabilities: u32,
+    noise_key_signature: [u8; 64],
+}
+
+struct PeerHandle {
+    info: NodeInformation,
+    /// Plaintext, `encode_frame`-encoded `Command`s bound for this peer; the session task
+    /// owns the actual `TransportState` and encrypts each one before it hits the wire.
+    outbound: UnboundedSender<Vec<u8>>,
+}
+
+/// Which side of the Noise_XX handshake this session plays, determined by whether the
+/// connection was dialed out (`connect`) or accepted (the pairing listener).
+enum Role {
+    Initiator,
+    Responder,
+}
+
+/// Pairing and replication over the LAN. Holds the connected peer set and forwards their
+/// `Command`s into the same channel local FFI calls use (`tx`), so `shared_for_commands`
+/// never has to distinguish local from remote mutations.
+pub struct PeerNetwork {
+    identity: Identity,
+    tx: UnboundedSender<Command>,
+    peers: Mutex<HashMap<SocketAddr, PeerHandle>>,
+    discovery_enabled: AtomicBool,
+    mdns: Mutex<Option<ServiceDaemon>>,
+    /// Download/revalidation-style background tasks (the pairing listener, peer sessions,
+    /// discovery browsing) are spawned onto this rather than via bare `tokio::spawn`, since
+    /// `connect`/`set_discovery_enabled` can be called from `sdxr_connect_peer`/
+    /// `sdxr_enable_discovery` on an arbitrary host thread that never entered the runtime
+    /// itself -- see `model_downloader::ModelDownloader` for the same pattern.
+    runtime: tokio::runtime::Handle,
+}
+
+impl PeerNetwork {
+    /// Creates the peer network and immediately starts listening for inbound pairings on
+    /// [`PAIRING_PORT`]. Returned as an `Arc` since every background task it spawns needs to
+    /// hold a reference back to `peers`/`tx` for the life of the session.
+    pub fn new(identity: Identity, tx: UnboundedSender<Command>, runtime: tokio::runtime::Handle) -> Arc<Self> {
+        let net = Arc::new(Self {
+            identity,
+            tx,
+            peers: Mutex::new(HashMap::new()),
+            discovery_enabled: AtomicBool::new(false),
+            mdns: Mutex::new(None),
+            runtime,
+        });
+        net.spawn_listener();
+        net
+    }
+
+    fn spawn_listener(self: &Arc<Self>) {
+        let net = Arc::clone(self);
+        self.runtime.spawn(async move {
+            let listener = match TcpListener::bind(("0.0.0.0", PAIRING_PORT)).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    eprintln!("[networking] Failed to bind pairing port {}: {}", PAIRING_PORT, e);
+                    return;
+                }
+            };
+            loop {
+                match listener.accept().await {
+                    Ok((stream, addr)) => {
+                        let net = Arc::clone(&net);
+                        tokio::spawn(async move {
+                            if let Err(e) = run_peer_session(net, stream, addr, Role::Responder).await {
+                                eprintln!("[networking] Inbound peer session with {} ended: {}", addr, e);
+                            }
+                        });
+                    }
+                    Err(e) => eprintln!("[networking] Accept failed on pairing port: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Initiates an encrypted tunnel to `addr`, exchanges the `NodeInformation` handshake, and
+    /// starts forwarding `Command`s both ways once it's established.
+    pub fn connect(self: &Arc<Self>, addr: SocketAddr) {
+        let net = Arc::clone(self);
+        self.runtime.spawn(async move {
+            match TcpStream::connect(addr).await {
+                Ok(stream) => {
+                    if let Err(e) = run_peer_session(net, stream, addr, Role::Initiator).await {
+                        eprintln!("[networking] Peer session with {} ended: {}", addr, e);
+                    }
+                }
+                Err(e) => eprintln!("[networking] Failed to connect to peer {}: {}", addr, e),
+            }
+        });
+    }
+
+    /// Currently paired peers' instance ids.
+    pub fn list(&self) -> Vec<u64> {
+        self.peers.lock().unwrap().values().map(|p| p.info.instance_id).collect()
+    }
+
+    fn is_paired(&self, addr: SocketAddr) -> bool {
+        self.peers.lock().unwrap().contains_key(&addr)
+    }
+
+    /// Enables or disables advertising this instance's pairing port (and browsing for other
+    /// instances') over mDNS. A resolved peer that isn't already paired is dialed
+    /// automatically via [`connect`](Self::connect), same as one entered by hand.
+    pub fn set_discovery_enabled(self: &Arc<Self>, enabled: bool) {
+        self.discovery_enabled.store(enabled, Ordering::SeqCst);
+        eprintln!("[networking] LAN discovery {}", if enabled { "enabled" } else { "disabled" });
+
+        let mut mdns = self.mdns.lock().unwrap();
+        if !enabled {
+            if let Some(daemon) = mdns.take() {
+                let _ = daemon.shutdown();
+            }
+            return;
+        }
+        if mdns.is_some() {
+            return; // already advertising/browsing
+        }
+
+        let daemon = match ServiceDaemon::new() {
+            Ok(daemon) => daemon,
+            Err(e) => {
+                eprintln!("[networking] Failed to start mDNS daemon: {}", e);
+                return;
+            }
+        };
+
+        let instance_name = format!("starworld-{:x}", self.identity.instance_id);
+        let mut properties = HashMap::new();
+        properties.insert("instance_id".to_string(), self.identity.instance_id.to_string());
+        match ServiceInfo::new(SERVICE_TYPE, &instance_name, &format!("{}.local.", instance_name), "", PAIRING_PORT, properties) {
+            Ok(service) => {
+                if let Err(e) = daemon.register(service) {
+                    eprintln!("[networking] Failed to register mDNS advertisement: {}", e);
+                }
+            }
+            Err(e) => eprintln!("[networking] Failed to build mDNS service info: {}", e),
+        }
+
+        match daemon.browse(SERVICE_TYPE) {
+            Ok(receiver) => self.spawn_discovery_browser(receiver),
+            Err(e) => eprintln!("[networking] Failed to browse for peers over mDNS: {}", e),
+        }
+
+        *mdns = Some(daemon);
+    }
+
+    fn spawn_discovery_browser(self: &Arc<Self>, receiver: mdns_sd::Receiver<ServiceEvent>) {
+        let net = Arc::clone(self);
+        self.runtime.spawn(async move {
+            while let Ok(event) = receiver.recv_async().await {
+                let ServiceEvent::ServiceResolved(info) = event else { continue };
+                if info.get_property_val_str("instance_id").and_then(|s| s.parse::<u64>().ok())
+                    == Some(net.identity.instance_id)
+                {
+                    continue; // our own advertisement
+                }
+                let Some(ip) = info.get_addresses().iter().next() else { continue };
+                let addr = SocketAddr::new((*ip).into(), info.get_port());
+                if !net.is_paired(addr) {
+                    net.connect(addr);
+                }
+            }
+        });
+    }
+
+    fn record_peer(&self, addr: SocketAddr, info: NodeInformation, outbound: UnboundedSender<Vec<u8>>) {
+        self.peers.lock().unwrap().insert(addr, PeerHandle { info, outbound });
+    }
+
+    fn remove_peer(&self, addr: SocketAddr) {
+        self.peers.lock().unwrap().remove(&addr);
+    }
+
+    /// Forwards a `Command` that cleared this instance's Lamport gate (local or replicated) to
+    /// every paired peer, encrypted over its own
+    /// Noise transport. Mirrors `replication::broadcast`, but for Noise-paired peers rather
+    /// than `sdxr_net_host`/`sdxr_net_connect` replication-hub clients.
+    pub fn broadcast(&self, cmd: &Command) {
+        let Some(frame) = encode_frame(cmd, 0) else { return };
+        for peer in self.peers.lock().unwrap().values() {
+            let _ = peer.outbound.send(frame.clone());
+        }
+    }
+}
+
+fn io_err(message: impl Into<String>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.into())
+}
+
+fn noise_err(e: snow::Error) -> std::io::Error {
+    io_err(format!("Noise error: {}", e))
+}
+
+async fn write_frame(write_half: &mut OwnedWriteHalf, payload: &[u8]) -> std::io::Result<()> {
+    write_half.write_all(&(payload.len() as u32).to_le_bytes()).await?;
+    write_half.write_all(payload).await
+}
+
+async fn read_frame(read_half: &mut OwnedReadHalf) -> std::io::Result<Vec<u8>> {
+    let mut len_bytes = [0u8; 4];
+    read_half.read_exact(&mut len_bytes).await?;
+    let len = u32::from_le_bytes(len_bytes) as usize;
+    let mut buf = vec![0u8; len];
+    read_half.read_exact(&mut buf).await?;
+    Ok(buf)
+}
+
+/// Runs the 3-message Noise_XX handshake (`-> e`, `<- e, ee, s, es`, `-> s, se`) over
+/// `read_half`/`write_half`, authenticating with this instance's persistent X25519 identity
+/// key. `Noise_XX` is the pattern meant for exactly this case: neither side needs to already
+/// know the other's static key, which fits two bridges pairing for the first time on a LAN.
+async fn do_handshake(
+    identity: &Identity,
+    role: &Role,
+    read_half: &mut OwnedReadHalf,
+    write_half: &mut OwnedWriteHalf,
+) -> std::io::Result<snow::TransportState> {
+    let builder = snow::Builder::new(NOISE_PARAMS.parse().map_err(|_| io_err("invalid Noise params"))?)
+        .local_private_key(&identity.noise_key.to_bytes());
+    let mut noise = match role {
+        Role::Initiator => builder.build_initiator().map_err(noise_err)?,
+        Role::Responder => builder.build_responder().map_err(noise_err)?,
+    };
+
+    let mut buf = vec![0u8; 65535];
+    match role {
+        Role::Initiator => {
+            let len = noise.write_message(&[], &mut buf).map_err(noise_err)?;
+            write_frame(write_half, &buf[..len]).await?;
+            let msg = read_frame(read_half).await?;
+            noise.read_message(&msg, &mut buf).map_err(noise_err)?;
+            let len = noise.write_message(&[], &mut buf).map_err(noise_err)?;
+            write_frame(write_half, &buf[..len]).await?;
+        }
+        Role::Responder => {
+            let msg = read_frame(read_half).await?;
+            noise.read_message(&msg, &mut buf).map_err(noise_err)?;
+            let len = noise.write_message(&[], &mut buf).map_err(noise_err)?;
+            write_frame(write_half, &buf[..len]).await?;
+            let msg = read_frame(read_half).await?;
+            noise.read_message(&msg, &mut buf).map_err(noise_err)?;
+        }
+    }
+
+    noise.into_transport_mode().map_err(noise_err)
+}
+
+/// Drives one paired peer connection end to end: the Noise_XX handshake, the identity-binding
+/// `NodeInformation` exchange, and then the steady-state frame loop that decrypts incoming
+/// frames into `Command`s (forwarded via `tx`, same as any local FFI mutation) and encrypts
+/// outgoing ones queued by [`PeerNetwork::broadcast`].
+async fn run_peer_session(net: Arc<PeerNetwork>, stream: TcpStream, addr: SocketAddr, role: Role) -> std::io::Result<()> {
+    let (mut read_half, mut write_half) = stream.into_split();
+    let mut transport = do_handshake(&net.identity, &role, &mut read_half, &mut write_half).await?;
+
+    let local_info = NodeInformation {
+        instance_id: net.identity.instance_id,
+        public_key: net.identity.public_key().to_bytes(),
+        display_name: format!("starworld-{:x}", net.identity.instance_id),
+        capabilities: 0,
+        noise_key_signature: net.identity.signing_key.sign(&net.identity.noise_public_key()).to_bytes(),
+    };
+    let local_info_json = serde_json::to_vec(&local_info).map_err(|e| io_err(e.to_string()))?;
+    let mut buf = vec![0u8; 65535];
+    let len = transport.write_message(&local_info_json, &mut buf).map_err(noise_err)?;
+    write_frame(&mut write_half, &buf[..len]).await?;
+
+    let msg = read_frame(&mut read_half).await?;
+    let len = transport.read_message(&msg, &mut buf).map_err(noise_err)?;
+    let remote_info: NodeInformation = serde_json::from_slice(&buf[..len]).map_err(|e| io_err(e.to_string()))?;
+
+    // The claimed `instance_id`/`display_name` are only trustworthy once `public_key` is
+    // proven to own the Noise static key that just authenticated this transport -- otherwise
+    // a peer could claim any identity it likes over an otherwise-legitimate encrypted tunnel.
+    let remote_static = transport.get_remote_static().ok_or_else(|| io_err("handshake completed without a remote static key"))?;
+    let verifying_key =
+        VerifyingKey::from_bytes(&remote_info.public_key).map_err(|_| io_err("malformed peer identity key"))?;
+    let signature = Signature::from_bytes(&remote_info.noise_key_signature);
+    verifying_key
+        .verify(remote_static, &signature)
+        .map_err(|_| io_err("peer identity key does not match its Noise static key"))?;
+    let instance_id = instance_id_from(&verifying_key);
+    let remote_info = NodeInformation { instance_id, ..remote_info };
+
+    let (outbound_tx, mut outbound_rx) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
+    net.record_peer(addr, remote_info, outbound_tx);
+    eprintln!("[networking] Paired with peer {} at {}", instance_id, addr);
+
+    let mut recv_buf = vec![0u8; 65535];
+    let mut send_buf = vec![0u8; 65535];
+    loop {
+        tokio::select! {
+            frame = read_frame(&mut read_half) => {
+                let Ok(frame) = frame else { break };
+                let Ok(len) = transport.read_message(&frame, &mut recv_buf) else {
+                    eprintln!("[networking] Dropping undecryptable frame from {}", addr);
+                    continue;
+                };
+                match decode_frame(&recv_buf[..len]) {
+                    Ok(cmd) => { let _ = net.tx.send(cmd); }
+                    Err(()) => eprintln!("[networking] Dropping malformed frame from {}", addr),
+                }
+            }
+            outgoing = outbound_rx.recv() => {
+                let Some(plaintext) = outgoing else { break };
+                match transport.write_message(&plaintext, &mut send_buf) {
+                    Ok(len) => {
+                        if write_frame(&mut write_half, &send_buf[..len]).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("[networking] Failed to encrypt outgoing frame for {}: {}", addr, e);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    net.remove_peer(addr);
+    eprintln!("[networking] Peer {} at {} disconnected", instance_id, addr);
+    Ok(())
+}
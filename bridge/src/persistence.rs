@@ -0,0 +1,172 @@
+// Scene persistence: saves `BridgeState`'s nodes to an embedded SQLite database so a scene
+// survives a restart instead of being discarded by `RootEvent::SaveState`. Writes are always
+// driven from a background task (see `sdxr_start`'s `persist_task`) so disk I/O never blocks
+// `on_frame` or the event loop.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use rusqlite::Connection;
+
+use crate::Node;
+
+/// Bumped whenever the persisted shape of a node changes; snapshots older than this are
+/// upgraded in [`SceneSnapshot::migrate`] before being handed back to `BridgeState`.
+///
+/// v2 added `Node::model_mesh`, an `Option<u64>` behind `#[serde(default)]` so v1 rows (which
+/// lack the field) still deserialize -- no data-shape migration is needed for it here.
+pub const SCHEMA_VERSION: u32 = 2;
+
+pub struct SceneSnapshot {
+    pub version: u32,
+    pub nodes: HashMap<u64, Node>,
+}
+
+impl SceneSnapshot {
+    /// Upgrades an older on-disk snapshot to `SCHEMA_VERSION`. The v1 -> v2 change (adding
+    /// `Node::model_mesh`) is backward-compatible via `#[serde(default)]`, so this is still a
+    /// no-op beyond stamping the version -- it's the hook a future, non-additive node shape
+    /// change should route an actual data transform through.
+    fn migrate(mut self) -> Self {
+        if self.version < SCHEMA_VERSION {
+            eprintln!("[persistence] Migrating persisted scene from schema v{} to v{}", self.version, SCHEMA_VERSION);
+            self.version = SCHEMA_VERSION;
+        }
+        self
+    }
+}
+
+/// Error type for [`PersistenceBackend`], boxed so backends as different as SQLite and LMDB
+/// don't have to share a concrete error representation.
+pub type PersistError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Seam a future backend (LMDB, say) implements instead of `SceneStore` being the only way to
+/// persist a scene. `SceneStore` itself keeps its inherent, `rusqlite`-flavored `save`/`load`
+/// (existing call sites are unaffected); this trait just forwards to them so code that wants to
+/// be backend-agnostic can hold a `dyn PersistenceBackend` instead of a concrete `SceneStore`.
+pub trait PersistenceBackend: Send + Sync {
+    fn save(&self, nodes: &HashMap<u64, Node>) -> Result<(), PersistError>;
+    fn load(&self) -> Result<SceneSnapshot, PersistError>;
+}
+
+/// Embedded SQLite-backed store holding a single row: the schema version plus every node,
+/// serialized as JSON. Swapping to LMDB later means adding a new [`PersistenceBackend`]
+/// implementation, not reworking this type or its callers.
+pub struct SceneStore {
+    conn: Mutex<Connection>,
+}
+
+impl SceneStore {
+    pub fn default_path() -> PathBuf {
+        dirs::data_dir().unwrap_or_else(|| PathBuf::from("/tmp")).join("starworld/scene.sqlite")
+    }
+
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS scene (
+                id INTEGER PRIMARY KEY CHECK (id = 0),
+                version INTEGER NOT NULL,
+                data TEXT NOT NULL
+            );",
+        )?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    /// Overwrites the persisted scene with `nodes` in one statement.
+    pub fn save(&self, nodes: &HashMap<u64, Node>) -> rusqlite::Result<()> {
+        let data = serde_json::to_string(nodes).expect("Node always serializes");
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO scene (id, version, data) VALUES (0, ?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET version = excluded.version, data = excluded.data",
+            rusqlite::params![SCHEMA_VERSION, data],
+        )?;
+        Ok(())
+    }
+
+    /// Loads the persisted scene, migrating it forward if it predates `SCHEMA_VERSION`.
+    /// Returns an empty snapshot if nothing has been saved yet.
+    pub fn load(&self) -> rusqlite::Result<SceneSnapshot> {
+        let conn = self.conn.lock().unwrap();
+        let row = conn
+            .query_row("SELECT version, data FROM scene WHERE id = 0", [], |row| {
+                Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?))
+            })
+            .ok();
+
+        let (version, nodes) = match row {
+            Some((version, data)) => {
+                let nodes = serde_json::from_str(&data).unwrap_or_else(|e| {
+                    eprintln!("[persistence] Persisted scene is corrupt, starting empty: {}", e);
+                    HashMap::new()
+                });
+                (version, nodes)
+            }
+            None => (SCHEMA_VERSION, HashMap::new()),
+        };
+
+        Ok(SceneSnapshot { version, nodes }.migrate())
+    }
+}
+
+impl PersistenceBackend for SceneStore {
+    fn save(&self, nodes: &HashMap<u64, Node>) -> Result<(), PersistError> {
+        SceneStore::save(self, nodes).map_err(Into::into)
+    }
+
+    fn load(&self) -> Result<SceneSnapshot, PersistError> {
+        SceneStore::load(self).map_err(Into::into)
+    }
+}
+
+/// Plain-JSON-file-backed store: the whole scene as one `{version, nodes}` document. No
+/// transactions, no schema migrations beyond `SceneSnapshot::migrate` -- for embedders that
+/// would rather diff/back up a scene as a text file than carry a SQLite dependency.
+pub struct JsonFileStore {
+    path: PathBuf,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct JsonSnapshot {
+    version: u32,
+    nodes: HashMap<u64, Node>,
+}
+
+impl JsonFileStore {
+    /// Doesn't touch disk until the first `save`/`load` -- unlike `SceneStore::open`, there's no
+    /// connection to establish or table to create up front.
+    pub fn open(path: &Path) -> Self {
+        Self { path: path.to_path_buf() }
+    }
+}
+
+impl PersistenceBackend for JsonFileStore {
+    fn save(&self, nodes: &HashMap<u64, Node>) -> Result<(), PersistError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let snapshot = JsonSnapshot { version: SCHEMA_VERSION, nodes: nodes.clone() };
+        let data = serde_json::to_vec(&snapshot)?;
+        std::fs::write(&self.path, data)?;
+        Ok(())
+    }
+
+    fn load(&self) -> Result<SceneSnapshot, PersistError> {
+        let snapshot = match std::fs::read(&self.path) {
+            Ok(data) => {
+                let parsed: JsonSnapshot = serde_json::from_slice(&data).unwrap_or_else(|e| {
+                    eprintln!("[persistence] Persisted scene at {} is corrupt, starting empty: {}", self.path.display(), e);
+                    JsonSnapshot { version: SCHEMA_VERSION, nodes: HashMap::new() }
+                });
+                SceneSnapshot { version: parsed.version, nodes: parsed.nodes }
+            }
+            Err(_) => SceneSnapshot { version: SCHEMA_VERSION, nodes: HashMap::new() },
+        };
+        Ok(snapshot.migrate())
+    }
+}
@@ -0,0 +1,261 @@
+// WASM plugin host: loads a WebAssembly module and grants it a narrow imported interface
+// mapping 1:1 onto the existing node FFI (create/update/remove/set_model/set_color/
+// set_dimensions/set_entity_type), all funneled through the same `Command`/`tx` channel local
+// FFI calls use. A plugin can't touch anything else -- no filesystem, no network, no node it
+// didn't create itself -- so a third party can extend scene behavior without linking into the
+// core library.
+//
+// Built on wasmtime's core module ABI rather than the full component-model/WIT toolchain (wit
+// bindgen isn't available in this build environment); the `env` imports registered below are
+// this host's narrow interface, playing the role a `.wit` world would in a full component build.
+//
+// This is a scope cut from the original request pending maintainer sign-off -- see
+// "Plugin host: core-module ABI instead of the WIT/component-model toolchain" in
+// /KNOWN_DEVIATIONS.md for what was asked for, what's here instead, and why.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use glam::Mat4;
+use tokio::sync::mpsc::UnboundedSender;
+use wasmtime::{Caller, Engine, Instance, Linker, Module, Store};
+
+use crate::Command;
+
+/// Node ids minted for a plugin are tagged in the high bit so they can never collide with the
+/// main `Ctrl::next_id` counter or a `replication`-namespaced remote id (both well under 2^63):
+/// bit 63 marks "plugin-owned", the next 31 bits are the owning plugin's handle, the low 32 are
+/// a per-plugin counter.
+fn plugin_node_id(handle: u64, local: u64) -> u64 {
+    (1u64 << 63) | ((handle & 0x7FFF_FFFF) << 32) | (local & 0xFFFF_FFFF)
+}
+
+/// Host state reachable from a plugin's imported functions: the shared command sender every
+/// mutation funnels through, this plugin's own Lamport clock, and which node ids it has
+/// created so `unload` can `Remove` exactly those and a plugin can't touch ids it doesn't own.
+struct PluginState {
+    tx: UnboundedSender<Command>,
+    handle: u64,
+    lamport: AtomicU64,
+    next_local_id: AtomicU64,
+    owned_ids: Mutex<HashSet<u64>>,
+}
+
+impl PluginState {
+    fn tick_lamport(&self) -> u64 {
+        self.lamport.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
+fn memory(caller: &mut Caller<'_, Arc<PluginState>>) -> Option<wasmtime::Memory> {
+    caller.get_export("memory")?.into_memory()
+}
+
+fn read_string(caller: &mut Caller<'_, Arc<PluginState>>, ptr: i32, len: i32) -> Option<String> {
+    if ptr < 0 || len < 0 {
+        return None;
+    }
+    let memory = memory(caller)?;
+    let start = ptr as usize;
+    let end = start.checked_add(len as usize)?;
+    let bytes = memory.data(caller).get(start..end)?;
+    String::from_utf8(bytes.to_vec()).ok()
+}
+
+fn read_mat4(caller: &mut Caller<'_, Arc<PluginState>>, ptr: i32) -> Option<Mat4> {
+    if ptr < 0 {
+        return None;
+    }
+    let memory = memory(caller)?;
+    let start = ptr as usize;
+    let end = start.checked_add(16 * 4)?;
+    let bytes = memory.data(caller).get(start..end)?;
+    let mut cols = [0.0f32; 16];
+    for (col, chunk) in cols.iter_mut().zip(bytes.chunks_exact(4)) {
+        *col = f32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    Some(Mat4::from_cols_array(&cols))
+}
+
+/// Registers this host's narrow `env` interface: node lifecycle and per-field setters, each
+/// funneling straight into `state.tx` with a freshly-ticked Lamport stamp like any local FFI
+/// call. Setters silently no-op on an id the plugin doesn't own, rather than trusting it to
+/// only ever pass back ids `create_node` gave it.
+fn register_imports(linker: &mut Linker<Arc<PluginState>>) -> wasmtime::Result<()> {
+    linker.func_wrap(
+        "env",
+        "create_node",
+        |mut caller: Caller<'_, Arc<PluginState>>, name_ptr: i32, name_len: i32, mat4_ptr: i32| -> i64 {
+            let Some(name) = read_string(&mut caller, name_ptr, name_len) else { return -1 };
+            let Some(transform) = read_mat4(&mut caller, mat4_ptr) else { return -1 };
+            let state = caller.data().clone();
+            let c_id = plugin_node_id(state.handle, state.next_local_id.fetch_add(1, Ordering::SeqCst));
+            state.owned_ids.lock().unwrap().insert(c_id);
+            let lamport = state.tick_lamport();
+            let _ = state.tx.send(Command::Create { c_id, name, transform, lamport });
+            c_id as i64
+        },
+    )?;
+
+    linker.func_wrap(
+        "env",
+        "update_node",
+        |mut caller: Caller<'_, Arc<PluginState>>, id: i64, mat4_ptr: i32| {
+            let Some(transform) = read_mat4(&mut caller, mat4_ptr) else { return };
+            let state = caller.data().clone();
+            let c_id = id as u64;
+            if !state.owned_ids.lock().unwrap().contains(&c_id) {
+                return;
+            }
+            let lamport = state.tick_lamport();
+            let _ = state.tx.send(Command::Update { c_id, transform, lamport });
+        },
+    )?;
+
+    linker.func_wrap("env", "remove_node", |caller: Caller<'_, Arc<PluginState>>, id: i64| {
+        let state = caller.data().clone();
+        let c_id = id as u64;
+        if !state.owned_ids.lock().unwrap().remove(&c_id) {
+            return;
+        }
+        let lamport = state.tick_lamport();
+        let _ = state.tx.send(Command::Remove { c_id, lamport });
+    })?;
+
+    linker.func_wrap(
+        "env",
+        "set_model",
+        |mut caller: Caller<'_, Arc<PluginState>>, id: i64, url_ptr: i32, url_len: i32| {
+            let Some(model_url) = read_string(&mut caller, url_ptr, url_len) else { return };
+            let state = caller.data().clone();
+            let c_id = id as u64;
+            if !state.owned_ids.lock().unwrap().contains(&c_id) {
+                return;
+            }
+            let lamport = state.tick_lamport();
+            let _ = state.tx.send(Command::SetModel { c_id, model_url, lamport });
+        },
+    )?;
+
+    linker.func_wrap(
+        "env",
+        "set_color",
+        |caller: Caller<'_, Arc<PluginState>>, id: i64, r: f32, g: f32, b: f32, a: f32| {
+            let state = caller.data().clone();
+            let c_id = id as u64;
+            if !state.owned_ids.lock().unwrap().contains(&c_id) {
+                return;
+            }
+            let lamport = state.tick_lamport();
+            let _ = state.tx.send(Command::SetColor { c_id, color: [r, g, b, a], lamport });
+        },
+    )?;
+
+    linker.func_wrap(
+        "env",
+        "set_dimensions",
+        |caller: Caller<'_, Arc<PluginState>>, id: i64, x: f32, y: f32, z: f32| {
+            let state = caller.data().clone();
+            let c_id = id as u64;
+            if !state.owned_ids.lock().unwrap().contains(&c_id) {
+                return;
+            }
+            let lamport = state.tick_lamport();
+            let _ = state.tx.send(Command::SetDimensions { c_id, dimensions: [x, y, z], lamport });
+        },
+    )?;
+
+    linker.func_wrap(
+        "env",
+        "set_entity_type",
+        |caller: Caller<'_, Arc<PluginState>>, id: i64, entity_type: i32| {
+            let state = caller.data().clone();
+            let c_id = id as u64;
+            if !state.owned_ids.lock().unwrap().contains(&c_id) {
+                return;
+            }
+            let lamport = state.tick_lamport();
+            let _ = state.tx.send(Command::SetEntityType { c_id, entity_type: entity_type as u8, lamport });
+        },
+    )?;
+
+    Ok(())
+}
+
+struct Plugin {
+    store: Store<Arc<PluginState>>,
+    instance: Instance,
+    state: Arc<PluginState>,
+}
+
+impl Plugin {
+    fn instantiate(bytes: &[u8], tx: UnboundedSender<Command>, handle: u64) -> wasmtime::Result<Self> {
+        let engine = Engine::default();
+        let module = Module::new(&engine, bytes)?;
+        let state = Arc::new(PluginState {
+            tx,
+            handle,
+            lamport: AtomicU64::new(0),
+            next_local_id: AtomicU64::new(0),
+            owned_ids: Mutex::new(HashSet::new()),
+        });
+        let mut store = Store::new(&engine, state.clone());
+        let mut linker = Linker::new(&engine);
+        register_imports(&mut linker)?;
+        let instance = linker.instantiate(&mut store, &module)?;
+        Ok(Self { store, instance, state })
+    }
+
+    /// Invokes the module's exported `tick(dt: f32)`. A module that doesn't export `tick`
+    /// (e.g. one that only reacts to host calls it makes during instantiation) is left idle,
+    /// not treated as an error.
+    fn tick(&mut self, dt: f32) -> wasmtime::Result<()> {
+        if let Ok(func) = self.instance.get_typed_func::<f32, ()>(&mut self.store, "tick") {
+            func.call(&mut self.store, dt)?;
+        }
+        Ok(())
+    }
+
+    fn unload(self) {
+        for c_id in self.state.owned_ids.lock().unwrap().drain() {
+            let lamport = self.state.tick_lamport();
+            let _ = self.state.tx.send(Command::Remove { c_id, lamport });
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref PLUGINS: Mutex<HashMap<u64, Plugin>> = Mutex::new(HashMap::new());
+}
+static NEXT_HANDLE: AtomicU64 = AtomicU64::new(1);
+
+/// Instantiates `bytes` as a plugin module and registers it under a freshly-allocated handle.
+pub(crate) fn load(bytes: &[u8], tx: UnboundedSender<Command>) -> Result<u64, String> {
+    let handle = NEXT_HANDLE.fetch_add(1, Ordering::SeqCst);
+    let plugin = Plugin::instantiate(bytes, tx, handle).map_err(|e| e.to_string())?;
+    PLUGINS.lock().unwrap().insert(handle, plugin);
+    Ok(handle)
+}
+
+/// Drives one frame of `handle`'s plugin. Returns `0` on success, or a negative code if
+/// `handle` doesn't name a loaded plugin or its `tick` export trapped.
+pub(crate) fn tick(handle: u64, dt: f32) -> i32 {
+    let mut plugins = PLUGINS.lock().unwrap();
+    let Some(plugin) = plugins.get_mut(&handle) else { return -1 };
+    match plugin.tick(dt) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("[plugins] tick for handle {} trapped: {}", handle, e);
+            -2
+        }
+    }
+}
+
+/// Unloads `handle`, `Remove`-ing every node it created. Returns `0` on success, `-1` if
+/// `handle` doesn't name a loaded plugin.
+pub(crate) fn unload(handle: u64) -> i32 {
+    let Some(plugin) = PLUGINS.lock().unwrap().remove(&handle) else { return -1 };
+    plugin.unload();
+    0
+}
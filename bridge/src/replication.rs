@@ -0,0 +1,400 @@
+// Command-stream network replication: turns every replicated-eligible `Command` into a compact
+// wire frame and streams it to connected peers over plain TCP, so `sdxr_net_host` /
+// `sdxr_net_connect` let multiple clients share one scene graph. Complements
+// `networking::PeerNetwork` (LAN pairing, Noise-encrypted, identity-authenticated) with a plain
+// client/server transport built directly on `Command` -- no pairing handshake beyond a client
+// id assignment, no encryption -- matching the delta-replication approach game-client crates
+// commonly use for world sync.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use glam::Mat4;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{BridgeState, Command, Node};
+
+/// Wire opcode for each replicated `Command` variant. `SetModelMesh` isn't replicated: its
+/// `mesh_handle` only names an entry in this process's own `gltf_loader` cache, meaningless to
+/// a peer that never parsed those bytes itself.
+#[repr(u8)]
+enum ReplOp {
+    Create = 0,
+    Update = 1,
+    SetModel = 2,
+    SetTexture = 3,
+    SetColor = 4,
+    SetDimensions = 5,
+    SetEntityType = 6,
+    Remove = 7,
+}
+
+/// Combines a connection's assigned client id with its own locally-minted node id so ids from
+/// different clients never collide on the wire: the low 48 bits are the local id, the high 16
+/// are the client id (`0` is reserved for whichever side is hosting).
+fn namespaced_id(client_id: u16, local_id: u64) -> u64 {
+    ((client_id as u64) << 48) | (local_id & 0x0000_FFFF_FFFF_FFFF)
+}
+
+fn push_string(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn read_u8(buf: &[u8], pos: &mut usize) -> Result<u8, ()> {
+    let byte = *buf.get(*pos).ok_or(())?;
+    *pos += 1;
+    Ok(byte)
+}
+
+fn read_u32(buf: &[u8], pos: &mut usize) -> Result<u32, ()> {
+    let end = pos.checked_add(4).ok_or(())?;
+    let bytes: [u8; 4] = buf.get(*pos..end).ok_or(())?.try_into().unwrap();
+    *pos = end;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(buf: &[u8], pos: &mut usize) -> Result<u64, ()> {
+    let end = pos.checked_add(8).ok_or(())?;
+    let bytes: [u8; 8] = buf.get(*pos..end).ok_or(())?.try_into().unwrap();
+    *pos = end;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f32(buf: &[u8], pos: &mut usize) -> Result<f32, ()> {
+    let end = pos.checked_add(4).ok_or(())?;
+    let bytes: [u8; 4] = buf.get(*pos..end).ok_or(())?.try_into().unwrap();
+    *pos = end;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+fn read_string(buf: &[u8], pos: &mut usize) -> Result<String, ()> {
+    let len = read_u32(buf, pos)? as usize;
+    let end = pos.checked_add(len).ok_or(())?;
+    let bytes = buf.get(*pos..end).ok_or(())?;
+    let s = String::from_utf8(bytes.to_vec()).map_err(|_| ())?;
+    *pos = end;
+    Ok(s)
+}
+
+/// True if `cmd`'s payload already matches `prev`'s corresponding field -- a resend that would
+/// replicate no actual change. `Create`/`Remove` always replicate.
+fn command_unchanged(cmd: &Command, prev: &Node) -> bool {
+    match cmd {
+        Command::Update { transform, .. } => *transform == prev.transform,
+        Command::SetModel { model_url, .. } => model_url == &prev.model_url,
+        Command::SetTexture { texture_url, .. } => texture_url == &prev.texture_url,
+        Command::SetColor { color, .. } => *color == prev.color,
+        Command::SetDimensions { dimensions, .. } => *dimensions == prev.dimensions,
+        Command::SetEntityType { entity_type, .. } => *entity_type == prev.entity_type,
+        _ => false,
+    }
+}
+
+/// Encodes `cmd` as a single frame (`1`-byte [`ReplOp`], `u64` namespaced id, `u64` lamport,
+/// then the opcode's payload). Returns `None` for commands this protocol doesn't replicate.
+/// `pub(crate)` so `networking`'s Noise-paired peer sessions can reuse the same wire format
+/// instead of re-deriving their own.
+pub(crate) fn encode_frame(cmd: &Command, client_id: u16) -> Option<Vec<u8>> {
+    let c_id = cmd.c_id()?;
+    let lamport = cmd.lamport()?;
+    let opcode = match cmd {
+        Command::Create { .. } => ReplOp::Create,
+        Command::Update { .. } => ReplOp::Update,
+        Command::SetModel { .. } => ReplOp::SetModel,
+        Command::SetTexture { .. } => ReplOp::SetTexture,
+        Command::SetColor { .. } => ReplOp::SetColor,
+        Command::SetDimensions { .. } => ReplOp::SetDimensions,
+        Command::SetEntityType { .. } => ReplOp::SetEntityType,
+        Command::Remove { .. } => ReplOp::Remove,
+        Command::SetModelMesh { .. } | Command::Shutdown => return None,
+    };
+
+    let mut out = Vec::new();
+    out.push(opcode as u8);
+    out.extend_from_slice(&namespaced_id(client_id, c_id).to_le_bytes());
+    out.extend_from_slice(&lamport.to_le_bytes());
+    match cmd {
+        Command::Create { name, transform, .. } => {
+            push_string(&mut out, name);
+            for col in transform.to_cols_array() {
+                out.extend_from_slice(&col.to_le_bytes());
+            }
+        }
+        Command::Update { transform, .. } => {
+            for col in transform.to_cols_array() {
+                out.extend_from_slice(&col.to_le_bytes());
+            }
+        }
+        Command::SetModel { model_url, .. } => push_string(&mut out, model_url),
+        Command::SetTexture { texture_url, .. } => push_string(&mut out, texture_url),
+        Command::SetColor { color, .. } => {
+            for c in color {
+                out.extend_from_slice(&c.to_le_bytes());
+            }
+        }
+        Command::SetDimensions { dimensions, .. } => {
+            for d in dimensions {
+                out.extend_from_slice(&d.to_le_bytes());
+            }
+        }
+        Command::SetEntityType { entity_type, .. } => out.push(*entity_type),
+        Command::Remove { .. } => {}
+        Command::SetModelMesh { .. } | Command::Shutdown => unreachable!(),
+    }
+    Some(out)
+}
+
+/// Decodes a frame produced by [`encode_frame`]. The wire id and lamport are carried through
+/// unchanged -- the receiving `cmd_task` runs the same Lamport last-writer-wins gate on it as
+/// any locally-originated command, so a replicated edit converges the same way a local one does.
+pub(crate) fn decode_frame(buf: &[u8]) -> Result<Command, ()> {
+    let mut pos = 0usize;
+    let opcode = read_u8(buf, &mut pos)?;
+    let c_id = read_u64(buf, &mut pos)?;
+    let lamport = read_u64(buf, &mut pos)?;
+
+    let cmd = if opcode == ReplOp::Create as u8 {
+        let name = read_string(buf, &mut pos)?;
+        let mut cols = [0.0f32; 16];
+        for v in cols.iter_mut() {
+            *v = read_f32(buf, &mut pos)?;
+        }
+        Command::Create { c_id, name, transform: Mat4::from_cols_array(&cols), lamport }
+    } else if opcode == ReplOp::Update as u8 {
+        let mut cols = [0.0f32; 16];
+        for v in cols.iter_mut() {
+            *v = read_f32(buf, &mut pos)?;
+        }
+        Command::Update { c_id, transform: Mat4::from_cols_array(&cols), lamport }
+    } else if opcode == ReplOp::SetModel as u8 {
+        Command::SetModel { c_id, model_url: read_string(buf, &mut pos)?, lamport }
+    } else if opcode == ReplOp::SetTexture as u8 {
+        Command::SetTexture { c_id, texture_url: read_string(buf, &mut pos)?, lamport }
+    } else if opcode == ReplOp::SetColor as u8 {
+        let mut color = [0.0f32; 4];
+        for v in color.iter_mut() {
+            *v = read_f32(buf, &mut pos)?;
+        }
+        Command::SetColor { c_id, color, lamport }
+    } else if opcode == ReplOp::SetDimensions as u8 {
+        let mut dimensions = [0.0f32; 3];
+        for v in dimensions.iter_mut() {
+            *v = read_f32(buf, &mut pos)?;
+        }
+        Command::SetDimensions { c_id, dimensions, lamport }
+    } else if opcode == ReplOp::SetEntityType as u8 {
+        Command::SetEntityType { c_id, entity_type: read_u8(buf, &mut pos)?, lamport }
+    } else if opcode == ReplOp::Remove as u8 {
+        Command::Remove { c_id, lamport }
+    } else {
+        return Err(());
+    };
+    Ok(cmd)
+}
+
+/// Deflate-compresses a batch of already-encoded frames: a `u32` frame count followed by that
+/// many length-prefixed frames, zlib-compressed as a whole. `broadcast` currently ships a batch
+/// of one per call -- coalescing several per flush, the way `persist_task` coalesces dirty-scene
+/// writes, is left for whenever a host needs to drive that many edits in one frame.
+fn compress_frames(frames: &[Vec<u8>]) -> Vec<u8> {
+    let mut plain = Vec::new();
+    plain.extend_from_slice(&(frames.len() as u32).to_le_bytes());
+    for frame in frames {
+        plain.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        plain.extend_from_slice(frame);
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&plain).expect("compressing an in-memory buffer cannot fail");
+    encoder.finish().expect("compressing an in-memory buffer cannot fail")
+}
+
+fn decompress_frames(compressed: &[u8]) -> Result<Vec<Command>, ()> {
+    let mut plain = Vec::new();
+    ZlibDecoder::new(compressed).read_to_end(&mut plain).map_err(|_| ())?;
+
+    let mut pos = 0usize;
+    let count = read_u32(&plain, &mut pos)? as usize;
+    let mut commands = Vec::with_capacity(count);
+    for _ in 0..count {
+        let len = read_u32(&plain, &mut pos)? as usize;
+        let end = pos.checked_add(len).ok_or(())?;
+        let frame = plain.get(pos..end).ok_or(())?;
+        commands.push(decode_frame(frame)?);
+        pos = end;
+    }
+    Ok(commands)
+}
+
+struct HubInner {
+    tx: UnboundedSender<Command>,
+    self_client_id: AtomicU16,
+    next_client_id: AtomicU16,
+    peers: Mutex<HashMap<u16, UnboundedSender<Vec<u8>>>>,
+    /// Commands decoded off the wire, waiting for `sdxr_net_poll` to drain them into `tx` --
+    /// kept separate from direct injection so the host's own frame loop decides when remote
+    /// mutations land, the same way `sdxr_poll_event` leaves event delivery to the host's pace.
+    inbound: Mutex<VecDeque<Command>>,
+}
+
+lazy_static::lazy_static! {
+    static ref HUB: Mutex<Option<Arc<HubInner>>> = Mutex::new(None);
+}
+
+/// Returns the active hub, creating it (bound to `tx`) on first use by either
+/// `sdxr_net_host` or `sdxr_net_connect`.
+fn hub(tx: &UnboundedSender<Command>) -> Arc<HubInner> {
+    let mut slot = HUB.lock().unwrap();
+    slot.get_or_insert_with(|| {
+        Arc::new(HubInner {
+            tx: tx.clone(),
+            self_client_id: AtomicU16::new(0),
+            next_client_id: AtomicU16::new(1),
+            peers: Mutex::new(HashMap::new()),
+            inbound: Mutex::new(VecDeque::new()),
+        })
+    })
+    .clone()
+}
+
+fn spawn_reader(hub: Arc<HubInner>, mut read_half: OwnedReadHalf) {
+    tokio::spawn(async move {
+        loop {
+            let mut len_bytes = [0u8; 4];
+            if read_half.read_exact(&mut len_bytes).await.is_err() {
+                break;
+            }
+            let len = u32::from_le_bytes(len_bytes) as usize;
+            let mut compressed = vec![0u8; len];
+            if read_half.read_exact(&mut compressed).await.is_err() {
+                break;
+            }
+            match decompress_frames(&compressed) {
+                Ok(commands) => hub.inbound.lock().unwrap().extend(commands),
+                Err(()) => eprintln!("[replication] Dropping malformed frame batch from peer"),
+            }
+        }
+    });
+}
+
+fn spawn_writer(client_id: u16, mut write_half: OwnedWriteHalf) -> UnboundedSender<Vec<u8>> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
+    tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            let header = (message.len() as u32).to_le_bytes();
+            if write_half.write_all(&header).await.is_err() || write_half.write_all(&message).await.is_err() {
+                eprintln!("[replication] Write to client {} failed, dropping connection", client_id);
+                break;
+            }
+        }
+    });
+    tx
+}
+
+/// Starts accepting replication clients on `port`. Each connection is assigned a client id
+/// (starting at 1; 0 is this instance's own namespace) sent as the first two bytes so the
+/// client can namespace the ids of nodes it creates locally.
+pub(crate) fn start_host(tx: &UnboundedSender<Command>, port: u16) -> std::io::Result<()> {
+    let hub = hub(tx);
+    let std_listener = std::net::TcpListener::bind(("0.0.0.0", port))?;
+    std_listener.set_nonblocking(true)?;
+    let listener = TcpListener::from_std(std_listener)?;
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, addr) = match listener.accept().await {
+                Ok(pair) => pair,
+                Err(e) => {
+                    eprintln!("[replication] Accept failed: {}", e);
+                    continue;
+                }
+            };
+            let client_id = hub.next_client_id.fetch_add(1, Ordering::SeqCst);
+            let (read_half, mut write_half) = stream.into_split();
+            if write_half.write_all(&client_id.to_le_bytes()).await.is_err() {
+                eprintln!("[replication] Failed to send handshake to {}", addr);
+                continue;
+            }
+            eprintln!("[replication] Client {} connected from {}", client_id, addr);
+            let peer_tx = spawn_writer(client_id, write_half);
+            hub.peers.lock().unwrap().insert(client_id, peer_tx);
+            spawn_reader(Arc::clone(&hub), read_half);
+        }
+    });
+    Ok(())
+}
+
+/// Dials `addr` ("host:port"), reads this instance's assigned client id from the host's
+/// handshake, and streams/receives frames like any other peer connection.
+pub(crate) fn start_client(tx: &UnboundedSender<Command>, addr: String) {
+    let hub = hub(tx);
+    tokio::spawn(async move {
+        let stream = match TcpStream::connect(&addr).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("[replication] Failed to connect to {}: {}", addr, e);
+                return;
+            }
+        };
+        let (mut read_half, write_half) = stream.into_split();
+        let mut id_bytes = [0u8; 2];
+        if read_half.read_exact(&mut id_bytes).await.is_err() {
+            eprintln!("[replication] {} closed before sending a handshake", addr);
+            return;
+        }
+        let client_id = u16::from_le_bytes(id_bytes);
+        hub.self_client_id.store(client_id, Ordering::SeqCst);
+
+        let peer_tx = spawn_writer(client_id, write_half);
+        hub.peers.lock().unwrap().insert(client_id, peer_tx);
+        spawn_reader(Arc::clone(&hub), read_half);
+        eprintln!("[replication] Connected to {} as client {}", addr, client_id);
+    });
+}
+
+/// Called from `cmd_task` for every command that clears the Lamport gate. No-ops if no
+/// `sdxr_net_host`/`sdxr_net_connect` is active, the variant isn't replicated, or the field
+/// already matches the last value held in the shared `BridgeState` for that node.
+pub(crate) fn broadcast(cmd: &Command, shared: &Arc<Mutex<BridgeState>>) {
+    let Some(hub) = HUB.lock().unwrap().clone() else { return };
+    let Some(c_id) = cmd.c_id() else { return };
+
+    if let Ok(state) = shared.lock() {
+        if let Some(prev) = state.nodes.get(&c_id) {
+            if command_unchanged(cmd, prev) {
+                return;
+            }
+        }
+    }
+
+    let client_id = hub.self_client_id.load(Ordering::SeqCst);
+    let Some(frame) = encode_frame(cmd, client_id) else { return };
+    let message = compress_frames(&[frame]);
+
+    for peer_tx in hub.peers.lock().unwrap().values() {
+        let _ = peer_tx.send(message.clone());
+    }
+}
+
+/// Drains every command decoded off the wire into `tx`, applying them the same way a local FFI
+/// call would. Returns the number applied; a no-op returning `0` if no hub is active.
+pub(crate) fn poll() -> i32 {
+    let Some(hub) = HUB.lock().unwrap().clone() else { return 0 };
+    let mut inbound = hub.inbound.lock().unwrap();
+    let mut applied = 0i32;
+    while let Some(cmd) = inbound.pop_front() {
+        if hub.tx.send(cmd).is_ok() {
+            applied += 1;
+        }
+    }
+    applied
+}
@@ -0,0 +1,212 @@
+// Binary (de)serialization for `sdxr_export_scene`/`sdxr_import_scene`: a compact, versioned
+// node stream handed directly across the FFI boundary as a buffer (a host-owned save slot, a
+// network transfer, ...) rather than written to a file Starworld manages itself the way
+// `persistence::SceneStore` does.
+
+use std::collections::HashMap;
+
+use glam::Mat4;
+
+use crate::Node;
+
+/// Bumped whenever the record layout below changes. [`decode`] rejects a blob whose leading
+/// version is not exactly this one rather than misinterpreting its bytes, so a blob written
+/// with a newer version is not readable here. Within a version, a record's own length prefix
+/// lets `decode` ignore trailing bytes a record carries beyond the fields it reads.
+pub const FORMAT_VERSION: u16 = 1;
+
+fn push_string(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn read_u8(buf: &[u8], pos: &mut usize) -> Result<u8, ()> {
+    let byte = *buf.get(*pos).ok_or(())?;
+    *pos += 1;
+    Ok(byte)
+}
+
+fn read_u32(buf: &[u8], pos: &mut usize) -> Result<u32, ()> {
+    let end = pos.checked_add(4).ok_or(())?;
+    let bytes: [u8; 4] = buf.get(*pos..end).ok_or(())?.try_into().unwrap();
+    *pos = end;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(buf: &[u8], pos: &mut usize) -> Result<u64, ()> {
+    let end = pos.checked_add(8).ok_or(())?;
+    let bytes: [u8; 8] = buf.get(*pos..end).ok_or(())?.try_into().unwrap();
+    *pos = end;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f32(buf: &[u8], pos: &mut usize) -> Result<f32, ()> {
+    let end = pos.checked_add(4).ok_or(())?;
+    let bytes: [u8; 4] = buf.get(*pos..end).ok_or(())?.try_into().unwrap();
+    *pos = end;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+fn read_string(buf: &[u8], pos: &mut usize) -> Result<String, ()> {
+    let len = read_u32(buf, pos)? as usize;
+    let end = pos.checked_add(len).ok_or(())?;
+    let bytes = buf.get(*pos..end).ok_or(())?;
+    let s = String::from_utf8(bytes.to_vec()).map_err(|_| ())?;
+    *pos = end;
+    Ok(s)
+}
+
+/// Encodes `nodes` as a leading `u16` [`FORMAT_VERSION`], a `u32` record count, and that many
+/// length-prefixed records -- the length prefix lets `decode` skip a record's trailing bytes
+/// past the fields it reads; a blob with a different leading version is still rejected whole.
+pub fn encode(nodes: &HashMap<u64, Node>) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    out.extend_from_slice(&(nodes.len() as u32).to_le_bytes());
+    for node in nodes.values() {
+        let mut record = Vec::new();
+        record.extend_from_slice(&node.id.to_le_bytes());
+        push_string(&mut record, &node.name);
+        for col in node.transform.to_cols_array() {
+            record.extend_from_slice(&col.to_le_bytes());
+        }
+        record.push(node.entity_type);
+        push_string(&mut record, &node.model_url);
+        push_string(&mut record, &node.texture_url);
+        for c in node.color {
+            record.extend_from_slice(&c.to_le_bytes());
+        }
+        for d in node.dimensions {
+            record.extend_from_slice(&d.to_le_bytes());
+        }
+        match node.model_mesh {
+            Some(handle) => {
+                record.push(1);
+                record.extend_from_slice(&handle.to_le_bytes());
+            }
+            None => record.push(0),
+        }
+
+        out.extend_from_slice(&(record.len() as u32).to_le_bytes());
+        out.extend_from_slice(&record);
+    }
+    out
+}
+
+/// Decodes a blob produced by [`encode`] back into the `Node`s it held.
+pub fn decode(buf: &[u8]) -> Result<Vec<Node>, ()> {
+    let mut pos = 0usize;
+    let version_bytes: [u8; 2] = buf.get(0..2).ok_or(())?.try_into().unwrap();
+    if u16::from_le_bytes(version_bytes) != FORMAT_VERSION {
+        return Err(());
+    }
+    pos += 2;
+    let count = read_u32(buf, &mut pos)? as usize;
+
+    let mut nodes = Vec::with_capacity(count);
+    for _ in 0..count {
+        let record_len = read_u32(buf, &mut pos)? as usize;
+        let record_end = pos.checked_add(record_len).ok_or(())?;
+        let record = buf.get(pos..record_end).ok_or(())?;
+        nodes.push(decode_record(record)?);
+        pos = record_end;
+    }
+    Ok(nodes)
+}
+
+fn decode_record(buf: &[u8]) -> Result<Node, ()> {
+    let mut pos = 0usize;
+    let id = read_u64(buf, &mut pos)?;
+    let name = read_string(buf, &mut pos)?;
+    let mut cols = [0.0f32; 16];
+    for v in cols.iter_mut() {
+        *v = read_f32(buf, &mut pos)?;
+    }
+    let entity_type = read_u8(buf, &mut pos)?;
+    let model_url = read_string(buf, &mut pos)?;
+    let texture_url = read_string(buf, &mut pos)?;
+    let mut color = [0.0f32; 4];
+    for v in color.iter_mut() {
+        *v = read_f32(buf, &mut pos)?;
+    }
+    let mut dimensions = [0.0f32; 3];
+    for v in dimensions.iter_mut() {
+        *v = read_f32(buf, &mut pos)?;
+    }
+    let model_mesh = match read_u8(buf, &mut pos)? {
+        1 => Some(read_u64(buf, &mut pos)?),
+        _ => None,
+    };
+
+    Ok(Node {
+        id,
+        name,
+        transform: Mat4::from_cols_array(&cols),
+        entity_type,
+        model_url,
+        texture_url,
+        color,
+        dimensions,
+        model_mesh,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_node(id: u64, model_mesh: Option<u64>) -> Node {
+        Node {
+            id,
+            name: format!("node-{}", id),
+            transform: Mat4::from_translation(glam::Vec3::new(1.0, 2.0, 3.0)),
+            entity_type: 2,
+            model_url: "https://example.com/model.glb".to_string(),
+            texture_url: String::new(),
+            color: [0.1, 0.2, 0.3, 1.0],
+            dimensions: [1.0, 2.0, 3.0],
+            model_mesh,
+        }
+    }
+
+    fn assert_nodes_eq(a: &Node, b: &Node) {
+        assert_eq!(a.id, b.id);
+        assert_eq!(a.name, b.name);
+        assert_eq!(a.transform.to_cols_array(), b.transform.to_cols_array());
+        assert_eq!(a.entity_type, b.entity_type);
+        assert_eq!(a.model_url, b.model_url);
+        assert_eq!(a.texture_url, b.texture_url);
+        assert_eq!(a.color, b.color);
+        assert_eq!(a.dimensions, b.dimensions);
+        assert_eq!(a.model_mesh, b.model_mesh);
+    }
+
+    #[test]
+    fn encode_decode_round_trips_every_field() {
+        let mut nodes = HashMap::new();
+        nodes.insert(1, sample_node(1, None));
+        nodes.insert(2, sample_node(2, Some(0xDEAD_BEEF)));
+
+        let decoded = decode(&encode(&nodes)).expect("a blob encode just produced should decode");
+
+        assert_eq!(decoded.len(), nodes.len());
+        for node in &decoded {
+            assert_nodes_eq(node, &nodes[&node.id]);
+        }
+    }
+
+    #[test]
+    fn decode_rejects_an_unrecognized_format_version() {
+        let mut blob = encode(&HashMap::new());
+        blob[0..2].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert!(decode(&blob).is_err());
+    }
+
+    #[test]
+    fn decode_rejects_a_truncated_blob() {
+        let mut nodes = HashMap::new();
+        nodes.insert(1, sample_node(1, None));
+        let blob = encode(&nodes);
+        assert!(decode(&blob[..blob.len() - 1]).is_err());
+    }
+}
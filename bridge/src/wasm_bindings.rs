@@ -0,0 +1,152 @@
+// wasm-bindgen binding layer for browser/WebXR hosts: mirrors the `sdxr_*` node calls in
+// lib.rs (create/update/remove, the per-field setters, node count), but with JS-friendly argument types (owned `String`s, `Float32Array`s)
+// in place of raw pointers, so a host never has to hand-roll the C calling convention from
+// JS. Funnels into the same `CTRL`/`Command` channel the native FFI uses, so a WebXR build
+// and a native build share one command/replication/persistence pipeline.
+//
+// Only compiled for `wasm32` targets behind the `wasm` feature, so native builds (and their
+// `std::thread`-based runtime in `sdxr_start_ex`) are completely untouched by this module.
+
+use std::sync::atomic::Ordering;
+
+use glam::Mat4;
+use wasm_bindgen::prelude::*;
+
+use crate::{Command, CTRL, STARTED};
+
+fn mat4_from_slice(cols: &[f32]) -> Result<Mat4, JsValue> {
+    if cols.len() != 16 {
+        return Err(JsValue::from_str("transform must be a 16-element Float32Array"));
+    }
+    let mut arr = [0.0f32; 16];
+    arr.copy_from_slice(cols);
+    Ok(Mat4::from_cols_array(&arr))
+}
+
+/// Thin JS-facing handle onto the bridge's node scene, mirroring the native `sdxr_*` node
+/// API. Carries no state of its own -- every method reaches through to the same `CTRL`
+/// singleton `sdxr_start`/`sdxr_start_ex` already set up, so there's at most one `Scene`
+/// worth constructing per page, same as there's one bridge per native process.
+#[wasm_bindgen]
+pub struct Scene;
+
+#[wasm_bindgen]
+impl Scene {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Scene {
+        Scene
+    }
+
+    /// Creates a node named `name` with the given 16-element column-major transform,
+    /// returning its id (or `0` if the bridge hasn't been started).
+    #[wasm_bindgen(js_name = createNode)]
+    pub fn create_node(&self, name: String, transform: &[f32]) -> Result<u64, JsValue> {
+        if !STARTED.load(Ordering::SeqCst) {
+            return Ok(0);
+        }
+        let transform = mat4_from_slice(transform)?;
+        let mut ctrl = CTRL.lock().unwrap();
+        let c_id = ctrl.next_id;
+        ctrl.next_id += 1;
+        let lamport = ctrl.tick();
+        if let Some(tx) = &ctrl.tx {
+            let _ = tx.send(Command::Create { c_id, name, transform, lamport });
+        }
+        Ok(c_id)
+    }
+
+    /// Replaces `id`'s transform with the given 16-element column-major `Float32Array`.
+    #[wasm_bindgen(js_name = updateNode)]
+    pub fn update_node(&self, id: u64, transform: &[f32]) -> Result<(), JsValue> {
+        if !STARTED.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        let transform = mat4_from_slice(transform)?;
+        let mut ctrl = CTRL.lock().unwrap();
+        let lamport = ctrl.tick();
+        if let Some(tx) = &ctrl.tx {
+            let _ = tx.send(Command::Update { c_id: id, transform, lamport });
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = removeNode)]
+    pub fn remove_node(&self, id: u64) {
+        if !STARTED.load(Ordering::SeqCst) {
+            return;
+        }
+        let mut ctrl = CTRL.lock().unwrap();
+        let lamport = ctrl.tick();
+        if let Some(tx) = &ctrl.tx {
+            let _ = tx.send(Command::Remove { c_id: id, lamport });
+        }
+    }
+
+    #[wasm_bindgen(js_name = setNodeModel)]
+    pub fn set_node_model(&self, id: u64, model_url: String) {
+        if !STARTED.load(Ordering::SeqCst) {
+            return;
+        }
+        let mut ctrl = CTRL.lock().unwrap();
+        let lamport = ctrl.tick();
+        if let Some(tx) = &ctrl.tx {
+            let _ = tx.send(Command::SetModel { c_id: id, model_url, lamport });
+        }
+    }
+
+    #[wasm_bindgen(js_name = setNodeTexture)]
+    pub fn set_node_texture(&self, id: u64, texture_url: String) {
+        if !STARTED.load(Ordering::SeqCst) {
+            return;
+        }
+        let mut ctrl = CTRL.lock().unwrap();
+        let lamport = ctrl.tick();
+        if let Some(tx) = &ctrl.tx {
+            let _ = tx.send(Command::SetTexture { c_id: id, texture_url, lamport });
+        }
+    }
+
+    #[wasm_bindgen(js_name = setNodeColor)]
+    pub fn set_node_color(&self, id: u64, r: f32, g: f32, b: f32, a: f32) {
+        if !STARTED.load(Ordering::SeqCst) {
+            return;
+        }
+        let mut ctrl = CTRL.lock().unwrap();
+        let lamport = ctrl.tick();
+        if let Some(tx) = &ctrl.tx {
+            let _ = tx.send(Command::SetColor { c_id: id, color: [r, g, b, a], lamport });
+        }
+    }
+
+    #[wasm_bindgen(js_name = setNodeDimensions)]
+    pub fn set_node_dimensions(&self, id: u64, x: f32, y: f32, z: f32) {
+        if !STARTED.load(Ordering::SeqCst) {
+            return;
+        }
+        let mut ctrl = CTRL.lock().unwrap();
+        let lamport = ctrl.tick();
+        if let Some(tx) = &ctrl.tx {
+            let _ = tx.send(Command::SetDimensions { c_id: id, dimensions: [x, y, z], lamport });
+        }
+    }
+
+    #[wasm_bindgen(js_name = setNodeEntityType)]
+    pub fn set_node_entity_type(&self, id: u64, entity_type: u8) {
+        if !STARTED.load(Ordering::SeqCst) {
+            return;
+        }
+        let mut ctrl = CTRL.lock().unwrap();
+        let lamport = ctrl.tick();
+        if let Some(tx) = &ctrl.tx {
+            let _ = tx.send(Command::SetEntityType { c_id: id, entity_type, lamport });
+        }
+    }
+
+    #[wasm_bindgen(js_name = nodeCount)]
+    pub fn node_count(&self) -> u64 {
+        if !STARTED.load(Ordering::SeqCst) {
+            return 0;
+        }
+        CTRL.lock().unwrap().nodes.len() as u64
+    }
+}